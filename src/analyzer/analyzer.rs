@@ -1,24 +1,49 @@
 use std::thread;
+use std::sync::Arc;
 use std::sync::mpsc::{channel, Receiver, Sender};
 
-use ffmpeg::{time, frame};
+use ffmpeg::frame;
 use male::{Window, Onset, Band};
 use male::window::filter;
 
-use analyzer::{beats, Beats, Channel, Event};
+use util::{Ring, Clock};
+use util::clock::Wall;
+use analyzer::{beats, Beats, Beat, Tempo, Bars, Envelope, Pitch, Key, Segment, Channel, Event};
+use analyzer::offline::Analysis;
+use analyzer::spectrum::{Samples, Spectrum};
+use analyzer::filter as local;
+use analyzer::detector::Detector;
 use settings::analyzer as settings;
-use settings::analyzer::Filter;
+use settings::analyzer::{Filter, Channels, Method};
 
 pub struct Analyzer {
 	settings: settings::Analyzer,
 
 	receiver: Receiver<Channel>,
 	sender:   Sender<frame::Audio>,
+	clock:    Arc<Clock>,
+	latency:  f64,
 
 	start:     f64,
+	primed:    f64,
 	timestamp: i64,
+	preloaded: bool,
+	detected:  bool,
+	shift:     f64,
+
+	paused: Option<f64>,
+	idle:   f64,
 
 	beats: Beats,
+	tempo: Tempo,
+	bars:  Bars,
+
+	envelopes: Ring<Envelope>,
+	pitches:   Ring<Pitch>,
+	segments:  Vec<Segment>,
+
+	chroma: [f64; 12],
+	key:    Option<Key>,
 }
 
 impl Analyzer {
@@ -30,47 +55,7 @@ impl Analyzer {
 			let settings = settings.clone();
 
 			thread::spawn(move || {
-				// The window handler.
-				let mut window = Window::new(settings.window().size(), 44100)
-					.with_hop(settings.window().hop());
-
-				match settings.window().filter() {
-					Filter::None => (),
-
-					Filter::Hamming =>
-						window = window.with_filter::<filter::Hamming, _>(..),
-				}
-
-				// The beat detector.
-				let mut onset = Onset::new(&window);
-
-				// Add bands from configuration.
-				for band in settings.beat().bands().iter().cloned() {
-					let low       = band.range().start;
-					let high      = band.range().end;
-					let threshold = (band.threshold().size(), band.threshold().sensitivity());
-
-					onset = onset.with_band(Band::<()>::new(low, high).with(band), Some(threshold));
-				}
-				
-				loop {
-					// Get the next frame.
-					let frame = ret!(frame_receiver.recv());
-
-					// Push the frame to the window.
-					window.push(frame.plane::<i16>(0));
-
-					// Get the next FFT channels, if any.
-					if let Ok(channels) = window.next() {
-						// Send the mono channel to the onset detector and send any peak as
-						// an event.
-						for peak in onset.analyze(&channels.mono()) {
-							if let Ok(peak) = peak {
-								event_sender.send(Channel::Mono(peak.offset(), Event::Beat(peak))).unwrap();
-							}
-						}
-					}
-				}
+				detect(&settings, frame_receiver.iter(), &event_sender);
 			});
 		}
 
@@ -79,14 +64,45 @@ impl Analyzer {
 
 			receiver: event_receiver,
 			sender:   frame_sender,
+			clock:    Arc::new(Wall),
+			latency:  0.0,
 
 			start:     0.0,
+			primed:    0.0,
 			timestamp: -1,
+			preloaded: false,
+			detected:  true,
+			shift:     0.0,
+
+			paused: None,
+			idle:   0.0,
 
 			beats: Beats::new(settings),
+			tempo: Tempo::new(settings.tempo()),
+			bars:  Bars::new(settings.bar()),
+
+			envelopes: Ring::new(1024),
+			pitches:   Ring::new(1024),
+			segments:  Vec::new(),
+
+			chroma: [0.0; 12],
+			key:    None,
 		}
 	}
 
+	/// Uses the given clock instead of the wall clock.
+	pub fn with_clock(mut self, clock: Arc<Clock>) -> Self {
+		self.clock = clock;
+		self
+	}
+
+	/// Moves every beat later by the given seconds, the time it takes for the
+	/// music to be heard.
+	pub fn with_latency(mut self, latency: f64) -> Self {
+		self.latency = latency;
+		self
+	}
+
 	pub fn settings(&self) -> &settings::Analyzer {
 		&self.settings
 	}
@@ -95,8 +111,93 @@ impl Analyzer {
 		self.start = time;
 	}
 
+	/// Tells the analyzer the first given seconds of samples are only there to
+	/// fill the threshold history, beats in them are dropped and the time is
+	/// moved forward by them.
+	pub fn prime(&mut self, duration: f64) {
+		// The fed frames are still used, they have to be moved to the time in
+		// the file.
+		if self.preloaded {
+			self.shift = self.primed - duration;
+			return;
+		}
+
+		self.primed = duration;
+		self.beats.skip(duration);
+	}
+
+	/// Stops the analyzer time at the given time.
+	pub fn pause(&mut self, time: f64) {
+		if self.paused.is_none() {
+			self.paused = Some(time);
+		}
+	}
+
+	/// Resumes the analyzer time at the given time.
+	pub fn resume(&mut self, time: f64) {
+		if let Some(paused) = self.paused.take() {
+			self.idle += time - paused;
+		}
+	}
+
+	/// The current analyzer time, the time spent paused is left out.
 	pub fn time(&self) -> f64 {
-		time::relative() as f64 / 1_000_000.0 - self.start
+		let now = self.paused.unwrap_or(self.clock.now());
+
+		self.at(now - self.idle)
+	}
+
+	/// Converts a game time, which doesn't include the time spent paused, to the
+	/// analyzer time.
+	pub fn at(&self, time: f64) -> f64 {
+		time - self.start + self.primed
+	}
+
+	/// Loads an analysis done ahead of time, the analyzer time becomes the
+	/// time in the file starting at `from` and beats from fed frames are
+	/// ignored.
+	pub fn preload(&mut self, analysis: Analysis, from: f64) {
+		self.seek(from);
+		self.detected = false;
+
+		for beat in analysis.beats {
+			self.beats.handle(&Channel::Mono(beat.offset(), Event::Beat(beat)).shift(self.latency));
+		}
+
+		self.segments = analysis.segments;
+		self.key      = analysis.key;
+	}
+
+	/// Loads the beats of a beatmap, replacing the other beats or adding to
+	/// them, the analyzer time becomes the time in the file starting at `from`.
+	pub fn chart(&mut self, beats: Vec<Beat>, from: f64, replace: bool) {
+		self.seek(from);
+
+		if replace {
+			self.beats.drain();
+			self.detected = false;
+		}
+
+		for beat in beats {
+			self.beats.handle(&Channel::Mono(beat.offset(), Event::Beat(beat.charted())).shift(self.latency));
+		}
+	}
+
+	/// Uses the given tempo map instead of estimating the tempo from the beats,
+	/// the times are in the file like with `chart`.
+	pub fn fix(&mut self, map: Vec<(f64, f64)>) {
+		self.tempo.fix(map);
+	}
+
+	/// Makes the analyzer time the time in the file starting at `from`.
+	fn seek(&mut self, from: f64) {
+		if self.preloaded {
+			return;
+		}
+
+		self.preloaded = true;
+		self.primed    = from;
+		self.beats.skip(from);
 	}
 
 	pub fn feed(&mut self, frame: frame::Audio) {
@@ -109,15 +210,216 @@ impl Analyzer {
 	}
 
 	fn fetch(&mut self) {
-		while let Ok(ref event) = self.receiver.try_recv() {
-			self.beats.handle(event);
+		while let Ok(event) = self.receiver.try_recv() {
+			match event {
+				Channel::Mono(_, Event::Envelope(envelope)) =>
+					self.envelopes.push(envelope.shift(self.shift)),
+
+				Channel::Mono(_, Event::Pitch(pitch)) => {
+					for (total, value) in self.chroma.iter_mut().zip(pitch.chroma()) {
+						*total += *value;
+					}
+
+					self.pitches.push(pitch.shift(self.shift));
+				},
+
+				// The beats are already there with an offline analysis, or they
+				// come from a beatmap.
+				_ if !self.detected =>
+					(),
+
+				event =>
+					self.beats.handle(&event.shift(self.shift + self.latency)),
+			}
 		}
 	}
 
+	/// The envelope of the window at the given analyzer time, if it's been
+	/// analyzed already.
+	pub fn envelope(&self, time: f64) -> Option<&Envelope> {
+		self.envelopes.iter().rev().find(|e| e.offset() <= time)
+	}
+
+	/// The pitch of the window at the given analyzer time, if it's been
+	/// analyzed already.
+	pub fn pitch(&self, time: f64) -> Option<&Pitch> {
+		self.pitches.iter().rev().find(|p| p.offset() <= time)
+	}
+
+	/// The segment of the song at the given analyzer time, the structure is
+	/// only known with an offline analysis.
+	pub fn segment(&self, time: f64) -> Option<&Segment> {
+		self.segments.iter().find(|s| s.contains(time))
+	}
+
+	/// The key at the given analyzer time, the one of its segment with an
+	/// offline analysis, otherwise the one of the whole song as heard so far.
+	pub fn key(&self, time: f64) -> Option<Key> {
+		self.segment(time).and_then(|s| s.key())
+			.or(self.key)
+			.or_else(|| Key::detect(&self.chroma))
+	}
+
+	/// The beats landing within the given seconds from now.
+	pub fn upcoming(&mut self, horizon: f64) -> Vec<Beat> {
+		let now = self.time();
+
+		self.fetch();
+		self.beats.upcoming(now, now + horizon)
+	}
+
 	pub fn beats(&mut self) -> beats::Result {
 		let now = self.time();
 
 		self.fetch();
-		self.beats.fetch(now)
+		let result = self.beats.fetch(now);
+
+		// The tempo only follows the beats that landed, so it's the same in
+		// realtime and with an offline analysis.
+		if let Ok(ref beats) = result {
+			for beat in beats {
+				self.tempo.handle(beat.offset());
+				self.bars.handle(&self.tempo, beat.offset());
+			}
+		}
+
+		result
+	}
+
+	/// The beats on the grid that passed since the last call, as `Event::Bar`.
+	pub fn bars(&mut self) -> Vec<Event> {
+		let now = self.time();
+
+		self.bars.fetch(&self.tempo, now)
+	}
+
+	/// The estimated tempo in beats per minute, if there have been enough beats.
+	pub fn tempo(&self) -> Option<f64> {
+		self.tempo.bpm()
+	}
+
+	/// The predicted next beat after the given analyzer time.
+	pub fn next_beat(&self, time: f64) -> Option<f64> {
+		self.tempo.next(time, 1)
+	}
+
+	/// Snaps the given analyzer time to the closest point on the beat grid,
+	/// split in the given subdivisions, 2 for eighth notes in 4/4 and so on.
+	pub fn quantize(&self, time: f64, subdivision: u32) -> Option<f64> {
+		self.tempo.quantize(time, subdivision)
+	}
+}
+
+/// Runs the beat detection over the given frames, sending every peak and the
+/// envelope and pitch of every window as events.
+pub fn detect<I>(settings: &settings::Analyzer, frames: I, sender: &Sender<Channel>)
+	where I: Iterator<Item = frame::Audio>
+{
+	// The window handler.
+	let mut window = Window::new(settings.window().size(), 44100)
+		.with_hop(settings.window().hop());
+
+	match settings.window().filter() {
+		Filter::None => (),
+
+		Filter::Hamming =>
+			window = window.with_filter::<filter::Hamming, _>(..),
+
+		Filter::Hann =>
+			window = window.with_filter::<local::Hann, _>(..),
+
+		Filter::Blackman =>
+			window = window.with_filter::<local::Blackman, _>(..),
+
+		Filter::BlackmanHarris =>
+			window = window.with_filter::<local::BlackmanHarris, _>(..),
+
+		Filter::FlatTop =>
+			window = window.with_filter::<local::FlatTop, _>(..),
+	}
+
+	// The beat detectors, the left and right ones are only used for stereo
+	// analysis.
+	let (mut mono, mut left, mut right) = {
+		let detector = || {
+			let mut onset = Onset::new(&window);
+
+			// Add bands from configuration, the ones with other methods have their
+			// own detectors.
+			for band in settings.beat().bands().iter().filter(|b| b.method() == Method::Default).cloned() {
+				let low       = band.range().start;
+				let high      = band.range().end;
+				let threshold = (band.threshold().size(), band.threshold().sensitivity());
+
+				onset = onset.with_band(Band::<()>::new(low, high).with(band), Some(threshold));
+			}
+
+			onset
+		};
+
+		(detector(), detector(), detector())
+	};
+
+	// The detectors for the bands not using male, they work on the mono mix.
+	let mut detectors = settings.beat().bands().iter()
+		.filter(|b| b.method() != Method::Default)
+		.map(Detector::new).collect::<Vec<_>>();
+
+	// The windows for the envelopes and the local detectors.
+	let mut samples = Samples::new(settings.window().size(), settings.window().hop(), 44100);
+
+	for frame in frames {
+		// Push the frame to the window.
+		window.push(frame.plane::<i16>(0));
+		samples.push(frame.plane::<i16>(0));
+
+		// Send the envelope and pitch of every window, and any beat from the
+		// local detectors.
+		while let Some((offset, chunk)) = samples.next() {
+			let spectrum = Spectrum::new(&chunk, 44100, settings.window().filter());
+
+			for detector in &mut detectors {
+				if let Some(beat) = detector.analyze(offset, &spectrum) {
+					ret!(sender.send(Channel::Mono(beat.offset(), Event::Beat(beat))));
+				}
+			}
+
+			ret!(sender.send(Channel::Mono(offset,
+				Event::Envelope(Envelope::new(settings, offset, &chunk, &spectrum)))));
+
+			ret!(sender.send(Channel::Mono(offset,
+				Event::Pitch(Pitch::new(offset, &spectrum)))));
+		}
+
+		// Get the next FFT channels, if any.
+		if let Ok(channels) = window.next() {
+			match settings.channels() {
+				// Send the mono channel to the onset detector and send any peak as
+				// an event.
+				Channels::Mono => {
+					for peak in mono.analyze(&channels.mono()) {
+						if let Ok(peak) = peak {
+							ret!(sender.send(Channel::Mono(peak.offset(), Event::Beat(Beat::from(peak)))));
+						}
+					}
+				},
+
+				// Send each channel to its own onset detector, the beats handler
+				// takes care of hits on both.
+				Channels::Stereo => {
+					for peak in left.analyze(&channels.left()) {
+						if let Ok(peak) = peak {
+							ret!(sender.send(Channel::Left(peak.offset(), Event::Beat(Beat::from(peak)))));
+						}
+					}
+
+					for peak in right.analyze(&channels.right()) {
+						if let Ok(peak) = peak {
+							ret!(sender.send(Channel::Right(peak.offset(), Event::Beat(Beat::from(peak)))));
+						}
+					}
+				},
+			}
+		}
 	}
 }
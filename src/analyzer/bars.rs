@@ -0,0 +1,123 @@
+use util::Ring;
+use settings::analyzer as settings;
+use analyzer::{Tempo, Event};
+
+/// How much the downbeat evidence of a position fades every time the grid
+/// passes it.
+const DECAY: f64 = 0.9;
+
+/// Walks the beat grid from the tempo and finds where bars start.
+///
+/// Every position in the bar collects the onsets landing close to the grid
+/// beats on it, the position with the most evidence is taken as the downbeat.
+#[derive(Debug)]
+pub struct Bars {
+	settings: settings::Bar,
+	weights:  Vec<f64>,
+
+	onsets: Ring<f64>,
+	grid:   Ring<(f64, usize)>,
+
+	last:  Option<f64>,
+	count: usize,
+	index: u32,
+}
+
+impl Bars {
+	pub fn new(settings: &settings::Bar) -> Self {
+		Bars {
+			settings: settings.clone(),
+			weights:  vec![0.0; settings.beats() as usize],
+
+			onsets: Ring::new(16),
+			grid:   Ring::new(4),
+
+			last:  None,
+			count: 0,
+			index: 0,
+		}
+	}
+
+	/// Adds an onset, counting it for the grid beat it landed close to.
+	pub fn handle(&mut self, tempo: &Tempo, offset: f64) {
+		let window = match tempo.period() {
+			Some(period) =>
+				period / 4.0,
+
+			None =>
+				return,
+		};
+
+		for &(time, position) in self.grid.iter() {
+			if (offset - time).abs() <= window {
+				self.weights[position] += 1.0;
+			}
+		}
+
+		self.onsets.push(offset);
+	}
+
+	/// The grid beats up to the given time since the last fetch.
+	pub fn fetch(&mut self, tempo: &Tempo, now: f64) -> Vec<Event> {
+		let mut result = Vec::new();
+
+		let period = match tempo.period() {
+			Some(period) =>
+				period,
+
+			None =>
+				return result,
+		};
+
+		let mut from = self.last.unwrap_or(now);
+
+		// Skip ahead half a beat so a change of phase doesn't give back the
+		// same beat twice.
+		while let Some(time) = tempo.next(from + period / 2.0, 1) {
+			if time > now {
+				break;
+			}
+
+			let beats    = self.settings.beats() as usize;
+			let position = self.count % beats;
+			let hits     = self.onsets.iter().filter(|&&o| (o - time).abs() <= period / 4.0).count();
+
+			self.weights[position] = self.weights[position] * DECAY + hits as f64;
+
+			let downbeat = self.downbeat();
+			let beat     = ((position + beats - downbeat) % beats) as u32;
+
+			if beat == 0 && self.count > 0 {
+				self.index += 1;
+			}
+
+			result.push(Event::Bar {
+				offset: time,
+				index:  self.index,
+				beat:   beat,
+			});
+
+			self.grid.push((time, position));
+			self.count += 1;
+
+			from = time;
+		}
+
+		self.last = Some(from);
+
+		result
+	}
+
+	/// The position in the bar with the most evidence of being the first.
+	fn downbeat(&self) -> usize {
+		let mut result = 0;
+
+		for (position, &weight) in self.weights.iter().enumerate() {
+			if weight > self.weights[result] {
+				result = position;
+			}
+		}
+
+		result
+	}
+}
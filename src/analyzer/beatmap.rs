@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use analyzer::Beat;
+use settings::{self, analyzer};
+
+/// The beatmap for the given input, the one in the settings or the one next
+/// to the input with a `.beatmap` extension, falling back to a MIDI file next
+/// to the input.
+pub fn path(input: &str, settings: &analyzer::Analyzer) -> PathBuf {
+	if let Some(path) = settings.beatmap() {
+		return path.to_path_buf();
+	}
+
+	let input = Path::new(input);
+
+	for extension in &["beatmap", "mid", "midi"] {
+		let path = input.with_extension(extension);
+
+		if path.exists() {
+			return path;
+		}
+	}
+
+	input.with_extension("beatmap")
+}
+
+/// Whether the beatmap is a Standard MIDI File.
+pub fn is_midi(path: &Path) -> bool {
+	match path.extension().and_then(|e| e.to_str()) {
+		Some("mid") | Some("midi") =>
+			true,
+
+		_ =>
+			false,
+	}
+}
+
+/// Loads the beats from a beatmap, every line is the time in seconds and the
+/// name of the band, optionally followed by `pattern=<name>` or
+/// `enemy=<name>`, empty lines and lines starting with `#` are ignored.
+///
+/// The patterns and enemies have to be in the game settings.
+pub fn load(path: &Path, settings: &analyzer::Analyzer, game: &settings::Game) -> io::Result<Vec<Beat>> {
+	let mut result = Vec::new();
+
+	for (number, line) in BufReader::new(try!(File::open(path))).lines().enumerate() {
+		let line = try!(line);
+		let line = line.trim();
+
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let mut parts = line.split_whitespace();
+
+		let offset = parts.next().and_then(|v| v.parse::<f64>().ok());
+		let band   = parts.next().and_then(|name|
+			settings.beat().bands().iter().find(|b| b.name() == Some(name)));
+
+		let mut beat = match (offset, band) {
+			(Some(offset), Some(band)) if offset.is_finite() =>
+				Beat::new(offset, band.clone()),
+
+			_ =>
+				return Err(malformed(number, "expected a time and the name of a band")),
+		};
+
+		for part in parts {
+			let mut pair = part.splitn(2, '=');
+
+			beat = match (pair.next(), pair.next()) {
+				(Some("pattern"), Some(name)) if game.pattern(name).is_some() =>
+					beat.with_pattern(name),
+
+				(Some("enemy"), Some(name)) if game.enemy(name).is_some() =>
+					beat.with_enemy(name),
+
+				(Some("pattern"), Some(name)) =>
+					return Err(malformed(number, &format!("unknown pattern `{}`", name))),
+
+				(Some("enemy"), Some(name)) =>
+					return Err(malformed(number, &format!("unknown enemy `{}`", name))),
+
+				_ =>
+					return Err(malformed(number, "expected `pattern=<name>` or `enemy=<name>`")),
+			};
+		}
+
+		result.push(beat);
+	}
+
+	result.sort_by(|a, b| a.offset().partial_cmp(&b.offset()).unwrap());
+
+	Ok(result)
+}
+
+fn malformed(number: usize, message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", number + 1, message))
+}
@@ -1,15 +1,18 @@
 use std::collections::HashMap;
 
-use male::onset::Peak;
-
-use super::{Channel, Event};
+use super::{Channel, Event, Beat, Side};
 use settings::analyzer as settings;
 
-pub type Result = ::std::result::Result<Vec<Peak<settings::Band>>, ()>;
+pub type Result = ::std::result::Result<Vec<Beat>, ()>;
+
+/// How close in seconds the same beat on both channels has to be to count as
+/// a single one in the center.
+const TOLERANCE: f64 = 0.025;
 
 pub struct Beats {
 	settings: settings::Analyzer,
-	peaks:    Vec<Peak<settings::Band>>,
+	peaks:    Vec<Beat>,
+	skip:     f64,
 
 	last:          f64,
 	last_for_band: HashMap<u64, f64>,
@@ -20,51 +23,83 @@ impl Beats {
 		Beats {
 			settings: settings.clone(),
 			peaks:    Vec::new(),
+			skip:     0.0,
 
 			last: 0.0,
 			last_for_band: HashMap::new(),
 		}
 	}
 
-	pub fn handle(&mut self, event: &Channel) {
-		if let &Channel::Mono(a, Event::Beat(ref peak)) = event {
-			match self.peaks.binary_search_by(|b| b.offset().partial_cmp(&a).unwrap()) {
-				Ok(index) | Err(index) =>
-					self.peaks.insert(index, peak.clone())
-			}
-		}
+	/// Drops any peak before the given offset.
+	pub fn skip(&mut self, offset: f64) {
+		self.skip = offset;
+		self.peaks.retain(|p| p.offset() >= offset);
 	}
 
-	pub fn fetch(&mut self, now: f64) -> Result {
-		let mut index = 0;
+	pub fn handle(&mut self, event: &Channel) {
+		let (a, side, peak) = match event {
+			&Channel::Mono(a, Event::Beat(ref peak)) =>
+				(a, Side::Center, peak),
+
+			&Channel::Left(a, Event::Beat(ref peak)) =>
+				(a, Side::Left, peak),
+
+			&Channel::Right(a, Event::Beat(ref peak)) =>
+				(a, Side::Right, peak),
 
-		while index < self.peaks.len() {
-			index += 1;
+			_ =>
+				return,
+		};
 
-			if now > self.peaks[index - 1].offset() {
-				break;
+		if a < self.skip {
+			return;
+		}
+
+		// The same beat on the other channel means it's in the center.
+		if side != Side::Center {
+			let other = self.peaks.iter_mut().find(|p|
+				p.side() != side && p.band() == peak.band() && (p.offset() - a).abs() <= TOLERANCE);
+
+			if let Some(other) = other {
+				other.side = Side::Center;
+				return;
 			}
 		}
 
-		let mut result = Vec::new();
+		match self.peaks.binary_search_by(|b| b.offset().partial_cmp(&a).unwrap()) {
+			Ok(index) | Err(index) =>
+				self.peaks.insert(index, peak.clone().with_side(side))
+		}
+	}
 
-		for peak in self.peaks.drain(0 .. index) {
-			let key = hash(&peak);
+	/// Takes out all the beats without throttling.
+	pub fn drain(&mut self) -> Vec<Beat> {
+		self.peaks.drain(..).collect()
+	}
 
-			// Check global throttiling.
-			if peak.offset() - self.settings.beat().throttle() <= self.last {
-				continue;
-			}
+	/// The beats after `from` up to `to` that haven't been fetched yet, going
+	/// through the same throttling `fetch` will, so they're the ones it's going
+	/// to return unless more beats show up in between.
+	pub fn upcoming(&self, from: f64, to: f64) -> Vec<Beat> {
+		let mut last          = self.last;
+		let mut last_for_band = self.last_for_band.clone();
+
+		self.peaks.iter()
+			.filter(|p| p.offset() > from && p.offset() <= to)
+			.filter(|p| throttle(&self.settings, p, &mut last, &mut last_for_band))
+			.cloned().collect()
+	}
 
-			// Check local throttling.
-			if peak.offset() - peak.band().throttle() <= *self.last_for_band.get(&key).unwrap_or(&0.0) {
-				continue;
-			}
+	pub fn fetch(&mut self, now: f64) -> Result {
+		// Only the beats that already landed, the rest have to wait.
+		let index = self.peaks.iter().position(|p| p.offset() > now).unwrap_or(self.peaks.len());
 
-			self.last = peak.offset();
-			self.last_for_band.insert(key, peak.offset());
+		let mut result = Vec::new();
 
-			result.push(peak);
+		for peak in self.peaks.drain(0 .. index) {
+			if throttle(&self.settings, &peak, &mut self.last, &mut self.last_for_band) {
+				result.push(peak);
+			}
 		}
 
 		if result.is_empty() {
@@ -76,7 +111,30 @@ impl Beats {
 	}
 }
 
-fn hash(peak: &Peak<settings::Band>) -> u64 {
+/// Whether the beat gets through the throttling, keeping track of it if so.
+fn throttle(settings: &settings::Analyzer, peak: &Beat, last: &mut f64, last_for_band: &mut HashMap<u64, f64>) -> bool {
+	let key = hash(peak);
+
+	// Charted beats are exactly what's wanted, chords included.
+	if !peak.is_charted() {
+		// Check global throttiling.
+		if peak.offset() - settings.beat().throttle() <= *last {
+			return false;
+		}
+
+		// Check local throttling.
+		if peak.offset() - peak.band().throttle() <= *last_for_band.get(&key).unwrap_or(&0.0) {
+			return false;
+		}
+	}
+
+	*last = peak.offset();
+	last_for_band.insert(key, peak.offset());
+
+	true
+}
+
+fn hash(peak: &Beat) -> u64 {
 	use std::hash::{Hash, Hasher, SipHasher};
 
 	let mut hash = SipHasher::new();
@@ -0,0 +1,274 @@
+use std::fs::{self, File};
+use std::io::{self, Write, BufRead, BufReader, BufWriter};
+use std::path::Path;
+use std::hash::{Hash, Hasher, SipHasher};
+use std::sync::mpsc::channel;
+
+use ffmpeg::{Error, frame};
+
+use source;
+use analyzer::{Beats, Channel};
+use analyzer::analyzer::detect;
+use analyzer::offline;
+use settings::analyzer::{self as settings, Source};
+
+/// The sensitivities the calibration looks between.
+const LOW:  f64 = 1.0;
+const HIGH: f64 = 4.0;
+
+/// How many times the range is halved.
+const STEPS: usize = 10;
+
+/// Picks the threshold sensitivity of every band with a target density, so it
+/// gets about as many beats per second as asked, loading them from the cache
+/// if the file has already been calibrated with the same settings.
+///
+/// Nothing is done when the beats only come from a beatmap.
+pub fn calibrate(path: &str, settings: &settings::Analyzer) -> Result<settings::Analyzer, Error> {
+	let targets = settings.beat().bands().iter().enumerate()
+		.filter_map(|(i, b)| b.threshold().density().map(|d| (i, d)))
+		.collect::<Vec<(usize, f64)>>();
+
+	if targets.is_empty() || settings.source() == Source::Beatmap {
+		return Ok(settings.clone());
+	}
+
+	let key = match hash(path, settings) {
+		Ok(key) =>
+			key,
+
+		Err(error) => {
+			warn!("calibrate: could not hash {}: {}", path, error);
+			return run(path, settings, &targets);
+		}
+	};
+
+	let cache = settings.cache().join(format!("{:016x}.calibrate", key));
+
+	if let Ok(result) = load(&cache, settings, &targets) {
+		debug!("calibrate: loaded {} sensitivities from {}", targets.len(), cache.display());
+
+		return Ok(result);
+	}
+
+	let result = try!(run(path, settings, &targets));
+
+	if let Err(error) = save(&cache, &result, &targets) {
+		warn!("calibrate: could not write {}: {}", cache.display(), error);
+	}
+
+	Ok(result)
+}
+
+/// The detection is run over and over on the same audio, the whole file with
+/// an offline analysis or its first seconds otherwise, bisecting the
+/// sensitivities of all the bands at once.
+fn run(path: &str, settings: &settings::Analyzer, targets: &[(usize, f64)]) -> Result<settings::Analyzer, Error> {
+	let mut audio = match source::spawn(path, true, 0.0).0 {
+		Ok(Some(audio)) =>
+			audio,
+
+		Ok(None) =>
+			return Ok(settings.clone()),
+
+		Err(error) =>
+			return Err(error),
+	};
+
+	let mut frames   = Vec::<frame::Audio>::new();
+	let mut duration = 0.0;
+
+	while let Some(frame) = audio.next() {
+		duration = audio.time(&frame);
+
+		if !settings.offline() && duration > settings.calibrate().duration() {
+			break;
+		}
+
+		frames.push(frame);
+	}
+
+	if duration <= 0.0 {
+		return Ok(settings.clone());
+	}
+
+	let mut ranges = vec![(LOW, HIGH); targets.len()];
+
+	for _ in 0 .. STEPS {
+		let counts = count(&middle(settings, targets, &ranges), &frames);
+
+		for (&(index, density), range) in targets.iter().zip(ranges.iter_mut()) {
+			let half = (range.0 + range.1) / 2.0;
+
+			// A higher sensitivity lets fewer peaks through.
+			if counts[index] as f64 / duration > density {
+				range.0 = half;
+			}
+			else {
+				range.1 = half;
+			}
+		}
+	}
+
+	let result = middle(settings, targets, &ranges);
+
+	for (&(index, density), &(low, high)) in targets.iter().zip(ranges.iter()) {
+		// The range never moved away from a bound, so the density is out of reach.
+		if low == LOW || high == HIGH {
+			warn!("calibrate: band {} cannot get {} beats per second, clamped to {}", index,
+				density, result.beat().bands()[index].threshold().sensitivity());
+		}
+
+		debug!("calibrate: band {} at {} for {} beats per second", index,
+			result.beat().bands()[index].threshold().sensitivity(), density);
+	}
+
+	Ok(result)
+}
+
+/// The cache key, the one of the offline analysis together with what decides
+/// how much of the file is listened to.
+fn hash(path: &str, settings: &settings::Analyzer) -> io::Result<u64> {
+	let mut hash = SipHasher::new();
+
+	try!(offline::hash(path, settings)).hash(&mut hash);
+	settings.offline().hash(&mut hash);
+	format!("{:?}", settings.calibrate()).hash(&mut hash);
+
+	Ok(hash.finish())
+}
+
+/// Loads the cached sensitivities, every line is the index of a band and its
+/// sensitivity.
+fn load(path: &Path, settings: &settings::Analyzer, targets: &[(usize, f64)]) -> io::Result<settings::Analyzer> {
+	let mut result = settings.clone();
+	let mut found  = 0;
+
+	for line in BufReader::new(try!(File::open(path))).lines() {
+		let line      = try!(line);
+		let mut parts = line.split(' ');
+
+		let index       = parts.next().and_then(|v| v.parse::<usize>().ok());
+		let sensitivity = parts.next().and_then(|v| v.parse::<f64>().ok());
+
+		match (index, sensitivity) {
+			(Some(index), Some(sensitivity)) if targets.iter().any(|&(i, _)| i == index) => {
+				result = result.with_sensitivity(index, sensitivity);
+				found += 1;
+			},
+
+			_ =>
+				return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed line")),
+		}
+	}
+
+	if found != targets.len() {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "missing bands"));
+	}
+
+	Ok(result)
+}
+
+fn save(path: &Path, settings: &settings::Analyzer, targets: &[(usize, f64)]) -> io::Result<()> {
+	if let Some(parent) = path.parent() {
+		try!(fs::create_dir_all(parent));
+	}
+
+	let mut file = BufWriter::new(try!(File::create(path)));
+
+	for &(index, _) in targets {
+		try!(writeln!(file, "{} {}", index, settings.beat().bands()[index].threshold().sensitivity()));
+	}
+
+	Ok(())
+}
+
+/// The settings with the sensitivities in the middle of their ranges.
+fn middle(settings: &settings::Analyzer, targets: &[(usize, f64)], ranges: &[(f64, f64)]) -> settings::Analyzer {
+	let mut result = settings.clone();
+
+	for (&(index, _), &(low, high)) in targets.iter().zip(ranges) {
+		result = result.with_sensitivity(index, (low + high) / 2.0);
+	}
+
+	result
+}
+
+/// Runs the detection over the frames and counts the beats in every band.
+fn count(settings: &settings::Analyzer, frames: &[frame::Audio]) -> Vec<usize> {
+	let (sender, receiver) = channel::<Channel>();
+
+	detect(settings, frames.iter().cloned(), &sender);
+
+	// Go through the beats handler so hits on both channels count once.
+	let mut beats = Beats::new(settings);
+
+	while let Ok(event) = receiver.try_recv() {
+		beats.handle(&event);
+	}
+
+	let mut result = vec![0; settings.beat().bands().len()];
+
+	for beat in beats.drain() {
+		if let Some(index) = settings.beat().bands().iter().position(|b| b == beat.band()) {
+			result[index] += 1;
+		}
+	}
+
+	result
+}
+
+/// Writes all the bands with the calibrated thresholds to paste in the
+/// settings, the bands replace the ones already there so every field is
+/// written.
+pub fn store(path: &Path, settings: &settings::Analyzer) -> io::Result<()> {
+	if let Some(parent) = path.parent() {
+		try!(fs::create_dir_all(parent));
+	}
+
+	let mut file = BufWriter::new(try!(File::create(path)));
+
+	for band in settings.beat().bands() {
+		try!(writeln!(file, "[[analyzer.beat.band]]"));
+
+		if let Some(name) = band.name() {
+			try!(writeln!(file, "name        = {:?}", name));
+		}
+
+		if band.pitched() {
+			try!(writeln!(file, "color       = \"pitch\""));
+		}
+		else if band.keyed() {
+			try!(writeln!(file, "color       = \"key\""));
+		}
+		else if let Some(color) = band.color() {
+			try!(writeln!(file, "color       = \"{}\"", color));
+		}
+
+		if let Some(enemy) = band.enemy() {
+			try!(writeln!(file, "enemy       = {:?}", enemy));
+		}
+
+		if let Some(pattern) = band.pattern() {
+			try!(writeln!(file, "pattern     = {:?}", pattern));
+		}
+
+		if let Some(track) = band.track() {
+			try!(writeln!(file, "track       = {}", track));
+		}
+
+		if let Some(channel) = band.channel() {
+			try!(writeln!(file, "channel     = {}", channel));
+		}
+
+		try!(writeln!(file, "range       = [{}, {}]", band.range().start, band.range().end));
+		try!(writeln!(file, "method      = {:?}", band.method().name()));
+		try!(writeln!(file, "throttle    = {:?}", band.throttle()));
+		try!(writeln!(file, "size        = {}", band.threshold().size()));
+		try!(writeln!(file, "sensitivity = {:.3}", band.threshold().sensitivity()));
+		try!(writeln!(file, "density     = false"));
+		try!(writeln!(file, ""));
+	}
+
+	Ok(())
+}
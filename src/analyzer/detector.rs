@@ -0,0 +1,139 @@
+use std::f64::consts::PI;
+
+use num::Complex;
+
+use util::Ring;
+use settings::analyzer as settings;
+use settings::analyzer::Method;
+use analyzer::Beat;
+use analyzer::spectrum::Spectrum;
+
+/// An onset detector for a band, for the detection functions male doesn't
+/// have.
+///
+/// Every window gets a detection value, a value is a peak when it's the
+/// highest of its neighbours and above the mean of the threshold history
+/// times the sensitivity, so peaks come out `size` windows late.
+#[derive(Debug)]
+pub struct Detector {
+	band: settings::Band,
+
+	previous: Vec<Complex<f64>>,
+	before:   Vec<Complex<f64>>,
+	values:   Ring<(f64, f64)>,
+}
+
+impl Detector {
+	pub fn new(band: &settings::Band) -> Self {
+		Detector {
+			band: band.clone(),
+
+			previous: Vec::new(),
+			before:   Vec::new(),
+			values:   Ring::new(band.threshold().size() * 2 + 1),
+		}
+	}
+
+	/// Analyzes the spectrum of the window at the given offset, returning a
+	/// beat if the window in the middle of the history is a peak.
+	pub fn analyze(&mut self, offset: f64, spectrum: &Spectrum) -> Option<Beat> {
+		let low  = spectrum.bin(self.band.range().start as f64);
+		let high = spectrum.bin(self.band.range().end as f64);
+		let bins = &spectrum.bins()[low .. high + 1];
+
+		let value = if self.previous.len() != bins.len() {
+			0.0
+		}
+		else {
+			match self.band.method() {
+				Method::Default | Method::Flux =>
+					flux(bins, &self.previous),
+
+				Method::Hfc =>
+					hfc(bins, low),
+
+				Method::Phase =>
+					if self.before.len() == bins.len() { phase(bins, &self.previous, &self.before) } else { 0.0 },
+
+				Method::Complex =>
+					if self.before.len() == bins.len() { complex(bins, &self.previous, &self.before) } else { 0.0 },
+			}
+		};
+
+		self.before   = ::std::mem::replace(&mut self.previous, bins.to_vec());
+		self.values.push((offset, value));
+
+		self.peak()
+	}
+
+	fn peak(&self) -> Option<Beat> {
+		let size = self.band.threshold().size();
+
+		if self.values.len() < size * 2 + 1 {
+			return None;
+		}
+
+		let (offset, value) = self.values[size];
+		let (_, before)     = self.values[size - 1];
+		let (_, after)      = self.values[size + 1];
+
+		let mean = self.values.iter().fold(0.0, |acc, &(_, v)| acc + v) / self.values.len() as f64;
+
+		if value > before && value >= after && value > mean * self.band.threshold().sensitivity() {
+			Some(Beat::new(offset, self.band.clone()))
+		}
+		else {
+			None
+		}
+	}
+}
+
+/// Spectral flux, how much the magnitudes grew, good for drums.
+fn flux(bins: &[Complex<f64>], previous: &[Complex<f64>]) -> f64 {
+	bins.iter().zip(previous).fold(0.0, |acc, (b, p)| {
+		let difference = b.norm() - p.norm();
+
+		if difference > 0.0 { acc + difference } else { acc }
+	})
+}
+
+/// High frequency content, the energy weighted by frequency, good for
+/// hi-hats.
+fn hfc(bins: &[Complex<f64>], low: usize) -> f64 {
+	bins.iter().enumerate().fold(0.0, |acc, (i, b)| acc + (low + i) as f64 * b.norm_sqr())
+}
+
+/// Phase deviation, how far the phases are from continuing steadily, good for
+/// soft tonal attacks.
+fn phase(bins: &[Complex<f64>], previous: &[Complex<f64>], before: &[Complex<f64>]) -> f64 {
+	let sum = bins.iter().zip(previous).zip(before).fold(0.0, |acc, ((b, p), q)|
+		acc + wrap(b.arg() - 2.0 * p.arg() + q.arg()).abs());
+
+	sum / bins.len() as f64
+}
+
+/// Complex domain, the distance from the bins predicted by the steady
+/// magnitude and phase of the previous ones, good for tonal attacks and
+/// changes.
+fn complex(bins: &[Complex<f64>], previous: &[Complex<f64>], before: &[Complex<f64>]) -> f64 {
+	bins.iter().zip(previous).zip(before).fold(0.0, |acc, ((b, p), q)| {
+		let angle     = 2.0 * p.arg() - q.arg();
+		let predicted = Complex::new(p.norm() * angle.cos(), p.norm() * angle.sin());
+
+		acc + (*b - predicted).norm()
+	})
+}
+
+/// Wraps the phase in the -π to π range.
+fn wrap(phase: f64) -> f64 {
+	let mut phase = phase % (2.0 * PI);
+
+	if phase > PI {
+		phase -= 2.0 * PI;
+	}
+	else if phase < -PI {
+		phase += 2.0 * PI;
+	}
+
+	phase
+}
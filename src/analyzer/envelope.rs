@@ -0,0 +1,59 @@
+use settings::analyzer as settings;
+use analyzer::spectrum::Spectrum;
+
+/// The loudness and spectral energy of a window, for everything that wants
+/// more than beats.
+#[derive(Clone, Debug)]
+pub struct Envelope {
+	offset:   f64,
+	rms:      f64,
+	bands:    Vec<f64>,
+	centroid: f64,
+}
+
+impl Envelope {
+	pub fn new(settings: &settings::Analyzer, offset: f64, samples: &[f64], spectrum: &Spectrum) -> Self {
+		let rms = if samples.is_empty() {
+			0.0
+		}
+		else {
+			(samples.iter().fold(0.0, |acc, s| acc + s * s) / samples.len() as f64).sqrt()
+		};
+
+		Envelope {
+			offset:   offset,
+			rms:      rms,
+			bands:    settings.beat().bands().iter().map(|b| spectrum.energy(b.range())).collect(),
+			centroid: spectrum.centroid(),
+		}
+	}
+
+	/// Moves the envelope in time.
+	pub fn shift(mut self, by: f64) -> Self {
+		self.offset += by;
+		self
+	}
+
+	#[inline(always)]
+	pub fn offset(&self) -> f64 {
+		self.offset
+	}
+
+	/// The loudness of the window, between 0.0 and 1.0.
+	#[inline(always)]
+	pub fn rms(&self) -> f64 {
+		self.rms
+	}
+
+	/// The energy in every band, in the order they're configured.
+	#[inline(always)]
+	pub fn bands(&self) -> &[f64] {
+		&self.bands
+	}
+
+	/// The spectral centroid in Hz, higher is brighter.
+	#[inline(always)]
+	pub fn centroid(&self) -> f64 {
+		self.centroid
+	}
+}
@@ -0,0 +1,72 @@
+use std::f64::consts::PI;
+
+use male::window::filter;
+use settings::analyzer::Filter;
+
+/// The coefficients of the cosine sum windows.
+const HANN:            &'static [f64] = &[0.5, 0.5];
+const HAMMING:         &'static [f64] = &[0.54, 0.46];
+const BLACKMAN:        &'static [f64] = &[0.42, 0.5, 0.08];
+const BLACKMAN_HARRIS: &'static [f64] = &[0.35875, 0.48829, 0.14128, 0.01168];
+const FLAT_TOP:        &'static [f64] = &[0.21557895, 0.41663158, 0.277263158, 0.083578947, 0.006947368];
+
+/// The weight of the sample at the given index in a window of the given size.
+pub fn coefficient(filter: Filter, index: usize, size: usize) -> f64 {
+	match filter {
+		Filter::None           => 1.0,
+		Filter::Hann           => cosine(HANN, index, size),
+		Filter::Hamming        => cosine(HAMMING, index, size),
+		Filter::Blackman       => cosine(BLACKMAN, index, size),
+		Filter::BlackmanHarris => cosine(BLACKMAN_HARRIS, index, size),
+		Filter::FlatTop        => cosine(FLAT_TOP, index, size),
+	}
+}
+
+/// A generalized cosine window, the terms alternate in sign.
+fn cosine(terms: &[f64], index: usize, size: usize) -> f64 {
+	if size < 2 {
+		return 1.0;
+	}
+
+	let x = 2.0 * PI * index as f64 / (size - 1) as f64;
+
+	terms.iter().enumerate().fold(0.0, |acc, (k, a)| {
+		let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+
+		acc + sign * a * (k as f64 * x).cos()
+	})
+}
+
+// male only ships Hamming, the rest go through its filter trait from here.
+
+pub struct Hann;
+
+impl filter::Filter for Hann {
+	fn compute(index: usize, size: usize) -> f64 {
+		cosine(HANN, index, size)
+	}
+}
+
+pub struct Blackman;
+
+impl filter::Filter for Blackman {
+	fn compute(index: usize, size: usize) -> f64 {
+		cosine(BLACKMAN, index, size)
+	}
+}
+
+pub struct BlackmanHarris;
+
+impl filter::Filter for BlackmanHarris {
+	fn compute(index: usize, size: usize) -> f64 {
+		cosine(BLACKMAN_HARRIS, index, size)
+	}
+}
+
+pub struct FlatTop;
+
+impl filter::Filter for FlatTop {
+	fn compute(index: usize, size: usize) -> f64 {
+		cosine(FLAT_TOP, index, size)
+	}
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+use std::str::FromStr;
+
+use util::Color;
+
+/// The Krumhansl-Kessler key profiles, starting from the tonic.
+const MAJOR: [f64; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR: [f64; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+const NAMES: [&'static str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Mode {
+	Major,
+	Minor,
+}
+
+/// A musical key.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Key {
+	tonic: u8,
+	mode:  Mode,
+}
+
+impl Key {
+	pub fn new(tonic: u8, mode: Mode) -> Self {
+		Key {
+			tonic: tonic % 12,
+			mode:  mode,
+		}
+	}
+
+	/// Finds the key best matching the chroma, if there's any energy in it.
+	pub fn detect(chroma: &[f64; 12]) -> Option<Key> {
+		if chroma.iter().all(|&v| v == 0.0) {
+			return None;
+		}
+
+		let mut result = None;
+		let mut best   = ::std::f64::NEG_INFINITY;
+
+		for tonic in 0 .. 12 {
+			for &(mode, profile) in &[(Mode::Major, &MAJOR), (Mode::Minor, &MINOR)] {
+				let score = correlation(chroma, profile, tonic);
+
+				if score > best {
+					best   = score;
+					result = Some(Key::new(tonic as u8, mode));
+				}
+			}
+		}
+
+		result
+	}
+
+	/// Major keys get warm colors and minor keys cool ones, close keys on the
+	/// circle of fifths get close hues.
+	pub fn color(&self) -> Color {
+		let fifths = (self.tonic as u32 * 7 % 12) as f32;
+
+		match self.mode {
+			Mode::Major =>
+				Color::hsv(fifths * 5.0, 0.8, 1.0),
+
+			Mode::Minor =>
+				Color::hsv(180.0 + fifths * 7.5, 0.7, 1.0),
+		}
+	}
+
+	/// The name of the key, like `C# minor`.
+	pub fn name(&self) -> String {
+		format!("{} {}", NAMES[self.tonic as usize], match self.mode {
+			Mode::Major => "major",
+			Mode::Minor => "minor",
+		})
+	}
+}
+
+/// Parses the `tonic:mode` format used by the cache.
+impl FromStr for Key {
+	type Err = ();
+
+	fn from_str(value: &str) -> Result<Key, ()> {
+		let mut parts = value.split(':');
+
+		let tonic = parts.next().and_then(|v| v.parse::<u8>().ok());
+		let mode  = match parts.next() {
+			Some("major") => Some(Mode::Major),
+			Some("minor") => Some(Mode::Minor),
+			_             => None,
+		};
+
+		match (tonic, mode) {
+			(Some(tonic), Some(mode)) if tonic < 12 =>
+				Ok(Key::new(tonic, mode)),
+
+			_ =>
+				Err(()),
+		}
+	}
+}
+
+impl fmt::Display for Key {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}:{}", self.tonic, match self.mode {
+			Mode::Major => "major",
+			Mode::Minor => "minor",
+		})
+	}
+}
+
+/// The Pearson correlation between the chroma and the profile rotated to the
+/// given tonic.
+fn correlation(chroma: &[f64; 12], profile: &[f64; 12], tonic: usize) -> f64 {
+	let mc = chroma.iter().fold(0.0, |acc, v| acc + v) / 12.0;
+	let mp = profile.iter().fold(0.0, |acc, v| acc + v) / 12.0;
+
+	let mut covariance = 0.0;
+	let mut vc         = 0.0;
+	let mut vp         = 0.0;
+
+	for i in 0 .. 12 {
+		let c = chroma[(i + tonic) % 12] - mc;
+		let p = profile[i] - mp;
+
+		covariance += c * p;
+		vc         += c * c;
+		vp         += p * p;
+	}
+
+	if vc == 0.0 || vp == 0.0 {
+		0.0
+	}
+	else {
+		covariance / (vc * vp).sqrt()
+	}
+}
@@ -0,0 +1,336 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use analyzer::Beat;
+use settings::analyzer as settings;
+
+/// The tempo until the first tempo change, 120 beats per minute.
+const TEMPO: u32 = 500_000;
+
+/// What a Standard MIDI File has for the game.
+#[derive(Clone, Debug)]
+pub struct Sequence {
+	/// The beats from the note-on events in tracks and channels mapped to
+	/// bands.
+	pub beats: Vec<Beat>,
+
+	/// The tempo map, every entry is the time in seconds a tempo starts at and
+	/// the length of its beats.
+	pub tempo: Vec<(f64, f64)>,
+}
+
+/// A note-on event, with the tick it's on, the track and the channel, both
+/// counting from zero.
+struct Note {
+	tick:    u64,
+	track:   u32,
+	channel: u8,
+}
+
+/// Loads a Standard MIDI File, turning the note-on events into beats for the
+/// bands their track or channel is mapped to.
+pub fn load(path: &Path, settings: &settings::Analyzer) -> io::Result<Sequence> {
+	let mut data = Vec::new();
+	try!(try!(File::open(path)).read_to_end(&mut data));
+
+	let mut reader = Reader::new(&data);
+
+	if try!(reader.take(4)) != b"MThd" {
+		return Err(malformed("not a MIDI file"));
+	}
+
+	let length   = try!(reader.u32());
+	let _format  = try!(reader.u16());
+	let tracks   = try!(reader.u16());
+	let division = try!(reader.u16());
+
+	if length < 6 {
+		return Err(malformed("header too short"));
+	}
+
+	try!(reader.take(length as usize - 6));
+
+	let mut notes  = Vec::new();
+	let mut tempos = Vec::new();
+	let mut track  = 0;
+
+	while track < tracks as u32 && !reader.is_empty() {
+		let id     = try!(reader.take(4));
+		let length = try!(reader.u32()) as usize;
+		let chunk  = try!(reader.take(length));
+
+		// Unknown chunks have to be skipped.
+		if id != b"MTrk" {
+			continue;
+		}
+
+		try!(parse(&mut Reader::new(chunk), track, &mut notes, &mut tempos));
+		track += 1;
+	}
+
+	tempos.sort_by(|a, b| a.0.cmp(&b.0));
+
+	let timing = try!(Timing::new(division, &tempos));
+	let mut beats = Vec::new();
+
+	for note in &notes {
+		if let Some(band) = settings.beat().bands().iter().find(|b| b.midi(note.track + 1, note.channel + 1)) {
+			beats.push(Beat::new(timing.seconds(note.tick), band.clone()));
+		}
+	}
+
+	beats.sort_by(|a, b| a.offset().partial_cmp(&b.offset()).unwrap());
+
+	let mut tempo = Vec::<(f64, f64)>::new();
+
+	for &(tick, value) in &tempos {
+		let start = timing.seconds(tick);
+
+		// Only the last change on the same tick counts.
+		if tempo.last().map(|&(s, _)| s == start).unwrap_or(false) {
+			tempo.pop();
+		}
+
+		tempo.push((start, value as f64 / 1_000_000.0));
+	}
+
+	if tempo.first().map(|&(s, _)| s > 0.0).unwrap_or(true) {
+		tempo.insert(0, (0.0, TEMPO as f64 / 1_000_000.0));
+	}
+
+	Ok(Sequence {
+		beats: beats,
+		tempo: tempo,
+	})
+}
+
+/// Goes through the events of a track, collecting the note-on events and the
+/// tempo changes.
+fn parse(reader: &mut Reader, track: u32, notes: &mut Vec<Note>, tempos: &mut Vec<(u64, u32)>) -> io::Result<()> {
+	let mut tick    = 0;
+	let mut running = None;
+
+	while !reader.is_empty() {
+		tick += try!(reader.variable());
+
+		let status = match try!(reader.peek()) {
+			byte if byte >= 0x80 => {
+				try!(reader.u8());
+				byte
+			},
+
+			// Running status, the data bytes follow straight away.
+			_ =>
+				match running {
+					Some(status) =>
+						status,
+
+					None =>
+						return Err(malformed("data without a status")),
+				},
+		};
+
+		match status {
+			// Meta event.
+			0xff => {
+				running = None;
+
+				let kind   = try!(reader.u8());
+				let length = try!(reader.variable()) as usize;
+				let data   = try!(reader.take(length));
+
+				// Tempo change, in microseconds per quarter note.
+				if kind == 0x51 && data.len() == 3 {
+					let tempo = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
+
+					if tempo == 0 {
+						return Err(malformed("zero tempo"));
+					}
+
+					tempos.push((tick, tempo));
+				}
+			},
+
+			// System exclusive event.
+			0xf0 | 0xf7 => {
+				running = None;
+
+				let length = try!(reader.variable()) as usize;
+				try!(reader.take(length));
+			},
+
+			_ => {
+				running = Some(status);
+
+				let channel = status & 0x0f;
+
+				match status & 0xf0 {
+					0x90 => {
+						let _note    = try!(reader.u8());
+						let velocity = try!(reader.u8());
+
+						// A note-on without velocity is a note-off.
+						if velocity > 0 {
+							notes.push(Note { tick: tick, track: track, channel: channel });
+						}
+					},
+
+					0x80 | 0xa0 | 0xb0 | 0xe0 => {
+						try!(reader.take(2));
+					},
+
+					0xc0 | 0xd0 => {
+						try!(reader.take(1));
+					},
+
+					_ =>
+						return Err(malformed("unknown event")),
+				}
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Converts ticks to seconds.
+struct Timing {
+	/// Ticks per quarter note, or zero with SMPTE timing.
+	ticks: u64,
+
+	/// Seconds per tick with SMPTE timing.
+	smpte: f64,
+
+	/// The tempo changes, with the tick, the seconds at it and the tempo.
+	changes: Vec<(u64, f64, u32)>,
+}
+
+impl Timing {
+	fn new(division: u16, tempos: &[(u64, u32)]) -> io::Result<Self> {
+		if division & 0x8000 != 0 {
+			// The frame rate is stored negated, 29 is 30 drop-frame.
+			let fps = match (division >> 8) as u8 as i8 {
+				-24 => 24.0,
+				-25 => 25.0,
+				-29 => 29.97,
+				-30 => 30.0,
+				_   => return Err(malformed("invalid SMPTE frame rate")),
+			};
+
+			let ticks = (division & 0xff) as f64;
+
+			if ticks == 0.0 {
+				return Err(malformed("zero ticks per frame"));
+			}
+
+			return Ok(Timing {
+				ticks:   0,
+				smpte:   1.0 / (fps * ticks),
+				changes: Vec::new(),
+			});
+		}
+
+		if division == 0 {
+			return Err(malformed("zero ticks per quarter note"));
+		}
+
+		let ticks       = division as u64;
+		let mut changes = vec![(0, 0.0, TEMPO)];
+
+		for &(tick, tempo) in tempos {
+			let (last, seconds, current) = changes[changes.len() - 1];
+			let seconds = seconds + (tick - last) as f64 * current as f64 / 1_000_000.0 / ticks as f64;
+
+			changes.push((tick, seconds, tempo));
+		}
+
+		Ok(Timing {
+			ticks:   ticks,
+			smpte:   0.0,
+			changes: changes,
+		})
+	}
+
+	fn seconds(&self, tick: u64) -> f64 {
+		if self.ticks == 0 {
+			return tick as f64 * self.smpte;
+		}
+
+		let &(start, seconds, tempo) = self.changes.iter().rev()
+			.find(|&&(t, _, _)| t <= tick).unwrap();
+
+		seconds + (tick - start) as f64 * tempo as f64 / 1_000_000.0 / self.ticks as f64
+	}
+}
+
+/// Reads big endian values out of a buffer.
+struct Reader<'a> {
+	data:     &'a [u8],
+	position: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn new(data: &'a [u8]) -> Self {
+		Reader {
+			data:     data,
+			position: 0,
+		}
+	}
+
+	fn is_empty(&self) -> bool {
+		self.position >= self.data.len()
+	}
+
+	fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+		if self.position + length > self.data.len() {
+			return Err(malformed("truncated file"));
+		}
+
+		let result = &self.data[self.position .. self.position + length];
+		self.position += length;
+
+		Ok(result)
+	}
+
+	fn peek(&self) -> io::Result<u8> {
+		self.data.get(self.position).cloned().ok_or_else(|| malformed("truncated file"))
+	}
+
+	fn u8(&mut self) -> io::Result<u8> {
+		Ok(try!(self.take(1))[0])
+	}
+
+	fn u16(&mut self) -> io::Result<u16> {
+		let data = try!(self.take(2));
+
+		Ok((data[0] as u16) << 8 | data[1] as u16)
+	}
+
+	fn u32(&mut self) -> io::Result<u32> {
+		let data = try!(self.take(4));
+
+		Ok((data[0] as u32) << 24 | (data[1] as u32) << 16 | (data[2] as u32) << 8 | data[3] as u32)
+	}
+
+	/// A variable length quantity, seven bits per byte with the high bit set
+	/// on all but the last.
+	fn variable(&mut self) -> io::Result<u64> {
+		let mut result = 0;
+
+		for _ in 0 .. 4 {
+			let byte = try!(self.u8());
+			result = result << 7 | (byte & 0x7f) as u64;
+
+			if byte & 0x80 == 0 {
+				return Ok(result);
+			}
+		}
+
+		Err(malformed("variable length quantity too long"))
+	}
+}
+
+fn malformed(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -8,9 +8,161 @@ pub enum Channel {
 	Mono(f64, Event),
 }
 
+impl Channel {
+	/// Moves the channel and any beat in it in time.
+	pub fn shift(self, by: f64) -> Self {
+		match self {
+			Channel::Left(offset, event) =>
+				Channel::Left(offset + by, event.shift(by)),
+
+			Channel::Right(offset, event) =>
+				Channel::Right(offset + by, event.shift(by)),
+
+			Channel::Mono(offset, event) =>
+				Channel::Mono(offset + by, event.shift(by)),
+		}
+	}
+}
+
 #[derive(Clone, Debug)]
 pub enum Event {
-	Beat(Peak<settings::Band>),
+	Beat(Beat),
+
+	/// The loudness and spectral energy of a window.
+	Envelope(Envelope),
+
+	/// The chroma and fundamental of a window.
+	Pitch(Pitch),
+
+	/// A beat on the grid, `index` is the bar it's in and `beat` where in the
+	/// bar it is, zero being the downbeat.
+	Bar {
+		offset: f64,
+		index:  u32,
+		beat:   u32,
+	},
+}
+
+impl Event {
+	/// Moves the event in time.
+	pub fn shift(self, by: f64) -> Self {
+		match self {
+			Event::Beat(beat) =>
+				Event::Beat(beat.shift(by)),
+
+			Event::Envelope(envelope) =>
+				Event::Envelope(envelope.shift(by)),
+
+			Event::Pitch(pitch) =>
+				Event::Pitch(pitch.shift(by)),
+
+			Event::Bar { offset, index, beat } =>
+				Event::Bar { offset: offset + by, index: index, beat: beat },
+		}
+	}
+}
+
+/// Where a beat has been heard.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Side {
+	Left,
+	Center,
+	Right,
+}
+
+/// A beat detected in a band, the offset is in seconds from the start of the
+/// analysis.
+///
+/// Beats from a beatmap can override the pattern and enemy of their band.
+#[derive(Clone, Debug)]
+pub struct Beat {
+	offset:  f64,
+	band:    settings::Band,
+	side:    Side,
+	pattern: Option<String>,
+	enemy:   Option<String>,
+	charted: bool,
+}
+
+impl Beat {
+	pub fn new(offset: f64, band: settings::Band) -> Self {
+		Beat {
+			offset:  offset,
+			band:    band,
+			side:    Side::Center,
+			pattern: None,
+			enemy:   None,
+			charted: false,
+		}
+	}
+
+	pub fn with_side(mut self, side: Side) -> Self {
+		self.side = side;
+		self
+	}
+
+	pub fn with_pattern<T: Into<String>>(mut self, pattern: T) -> Self {
+		self.pattern = Some(pattern.into());
+		self
+	}
+
+	pub fn with_enemy<T: Into<String>>(mut self, enemy: T) -> Self {
+		self.enemy = Some(enemy.into());
+		self
+	}
+
+	/// Marks the beat as coming from a chart, so it's never throttled.
+	pub fn charted(mut self) -> Self {
+		self.charted = true;
+		self
+	}
+
+	/// Moves the beat in time.
+	pub fn shift(mut self, by: f64) -> Self {
+		self.offset += by;
+		self
+	}
+
+	#[inline(always)]
+	pub fn offset(&self) -> f64 {
+		self.offset
+	}
+
+	#[inline(always)]
+	pub fn band(&self) -> &settings::Band {
+		&self.band
+	}
+
+	/// Where the beat has been heard, it's always the center with a mono
+	/// analysis.
+	#[inline(always)]
+	pub fn side(&self) -> Side {
+		self.side
+	}
+
+	/// Whether the beat comes from a beatmap or a MIDI file instead of the
+	/// detection.
+	#[inline(always)]
+	pub fn is_charted(&self) -> bool {
+		self.charted
+	}
+
+	/// The pattern fired on the beat, the one of the band unless overridden.
+	pub fn pattern(&self) -> Option<&str> {
+		self.pattern.as_ref().map(|p| p.as_ref()).or(self.band.pattern())
+	}
+
+	/// The enemy archetype spawned on the beat, the one of the band unless
+	/// overridden.
+	pub fn enemy(&self) -> Option<&str> {
+		self.enemy.as_ref().map(|e| e.as_ref()).or(self.band.enemy())
+	}
+}
+
+impl From<Peak<settings::Band>> for Beat {
+	fn from(peak: Peak<settings::Band>) -> Beat {
+		Beat::new(peak.offset(), peak.band().clone())
+	}
 }
 
 pub use male::Band;
@@ -18,5 +170,32 @@ pub use male::Band;
 mod beats;
 pub use self::beats::Beats;
 
+pub mod filter;
+pub mod spectrum;
+pub mod detector;
+
+mod envelope;
+pub use self::envelope::Envelope;
+
+mod pitch;
+pub use self::pitch::Pitch;
+
+mod key;
+pub use self::key::{Key, Mode};
+
+mod tempo;
+pub use self::tempo::Tempo;
+
+mod bars;
+pub use self::bars::Bars;
+
 mod analyzer;
 pub use self::analyzer::Analyzer;
+
+mod structure;
+pub use self::structure::{Segment, LABELS};
+
+pub mod offline;
+pub mod calibrate;
+pub mod beatmap;
+pub mod midi;
@@ -0,0 +1,238 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write, BufRead, BufReader, BufWriter};
+use std::path::Path;
+use std::hash::{Hash, Hasher, SipHasher};
+use std::sync::mpsc::channel;
+
+use ffmpeg::Error;
+
+use source;
+use analyzer::{Beats, Beat, Side, Pitch, Key, Segment, Channel, Event};
+use analyzer::analyzer::detect;
+use analyzer::structure;
+use settings::analyzer as settings;
+
+/// What the offline analysis found in the whole file.
+#[derive(Clone, Debug)]
+pub struct Analysis {
+	pub beats:    Vec<Beat>,
+	pub segments: Vec<Segment>,
+	pub key:      Option<Key>,
+}
+
+/// Analyzes the whole file, loading the analysis from the cache if the file
+/// has already been analyzed with the same settings.
+pub fn analysis(path: &str, settings: &settings::Analyzer) -> Result<Analysis, Error> {
+	let key = match hash(path, settings) {
+		Ok(key) =>
+			key,
+
+		Err(error) => {
+			warn!("offline: could not hash {}: {}", path, error);
+			return analyze(path, settings);
+		}
+	};
+
+	let cache = settings.cache().join(format!("{:016x}", key));
+
+	if let Ok(analysis) = load(&cache, settings) {
+		debug!("offline: loaded {} beats and {} segments from {}",
+			analysis.beats.len(), analysis.segments.len(), cache.display());
+
+		return Ok(analysis);
+	}
+
+	let analysis = try!(analyze(path, settings));
+
+	if let Err(error) = store(&cache, &analysis, settings) {
+		warn!("offline: could not write {}: {}", cache.display(), error);
+	}
+
+	Ok(analysis)
+}
+
+/// Decodes the whole audio stream and runs the beat detection over it, then
+/// splits it in segments from the envelopes and finds the key of the song
+/// and of every segment from the pitches.
+pub fn analyze(path: &str, settings: &settings::Analyzer) -> Result<Analysis, Error> {
+	let mut audio = match source::spawn(path, true, 0.0).0 {
+		Ok(Some(audio)) =>
+			audio,
+
+		Ok(None) =>
+			return Ok(Analysis { beats: Vec::new(), segments: Vec::new(), key: None }),
+
+		Err(error) =>
+			return Err(error),
+	};
+
+	let (sender, receiver) = channel::<Channel>();
+	let frames = ::std::iter::repeat(()).map(|_| audio.next())
+		.take_while(|f| f.is_some()).map(|f| f.unwrap());
+
+	detect(settings, frames, &sender);
+
+	// Go through the beats handler so hits on both channels are merged, then
+	// take everything out without throttling.
+	let mut beats     = Beats::new(settings);
+	let mut envelopes = Vec::new();
+	let mut pitches   = Vec::new();
+
+	while let Ok(event) = receiver.try_recv() {
+		match event {
+			Channel::Mono(_, Event::Envelope(envelope)) =>
+				envelopes.push(envelope),
+
+			Channel::Mono(_, Event::Pitch(pitch)) =>
+				pitches.push(pitch),
+
+			event =>
+				beats.handle(&event),
+		}
+	}
+
+	let segments = structure::segments(&envelopes).into_iter().map(|segment| {
+		let key = key(pitches.iter().filter(|p| segment.contains(p.offset())));
+		segment.with_key(key)
+	}).collect();
+
+	Ok(Analysis {
+		beats:    beats.drain(),
+		segments: segments,
+		key:      key(pitches.iter()),
+	})
+}
+
+/// The key from the chroma of the given pitches summed together.
+fn key<'a, I: Iterator<Item = &'a Pitch>>(pitches: I) -> Option<Key> {
+	let mut chroma = [0.0; 12];
+
+	for pitch in pitches {
+		for (total, value) in chroma.iter_mut().zip(pitch.chroma()) {
+			*total += *value;
+		}
+	}
+
+	Key::detect(&chroma)
+}
+
+/// The cache key, the file contents and the settings affecting the detection
+/// are hashed together.
+pub fn hash(path: &str, settings: &settings::Analyzer) -> io::Result<u64> {
+	let mut hash   = SipHasher::new();
+	let mut file   = try!(File::open(path));
+	let mut buffer = vec![0u8; 64 * 1024];
+
+	loop {
+		let read = try!(file.read(&mut buffer));
+
+		if read == 0 {
+			break;
+		}
+
+		buffer[.. read].hash(&mut hash);
+	}
+
+	format!("{:?}", settings.channels()).hash(&mut hash);
+	format!("{:?}", settings.window()).hash(&mut hash);
+	format!("{:?}", settings.beat()).hash(&mut hash);
+
+	Ok(hash.finish())
+}
+
+/// Loads the cached analysis, every line is either a beat with its offset,
+/// the index of the band and the side, a segment with its start, end,
+/// intensity, label and key, or the key of the song.
+fn load(path: &Path, settings: &settings::Analyzer) -> io::Result<Analysis> {
+	let mut result = Analysis { beats: Vec::new(), segments: Vec::new(), key: None };
+
+	for line in BufReader::new(try!(File::open(path))).lines() {
+		let line      = try!(line);
+		let mut parts = line.split(' ');
+
+		match parts.next() {
+			Some("beat") => {
+				let offset = parts.next().and_then(|v| v.parse::<f64>().ok());
+				let band   = parts.next().and_then(|v| v.parse::<usize>().ok())
+					.and_then(|i| settings.beat().bands().get(i));
+
+				let side = match parts.next() {
+					Some("l") => Some(Side::Left),
+					Some("c") => Some(Side::Center),
+					Some("r") => Some(Side::Right),
+					_         => None,
+				};
+
+				match (offset, band, side) {
+					(Some(offset), Some(band), Some(side)) =>
+						result.beats.push(Beat::new(offset, band.clone()).with_side(side)),
+
+					_ =>
+						return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed beat")),
+				}
+			},
+
+			Some("segment") => {
+				let start     = parts.next().and_then(|v| v.parse::<f64>().ok());
+				let end       = parts.next().and_then(|v| v.parse::<f64>().ok());
+				let intensity = parts.next().and_then(|v| v.parse::<f64>().ok());
+				let label     = parts.next();
+				let key       = parts.next().map(|v| v.parse::<Key>().ok());
+
+				match (start, end, intensity, label, key) {
+					(Some(start), Some(end), Some(intensity), Some(label), Some(key)) =>
+						result.segments.push(Segment::new(start, end, intensity, label).with_key(key)),
+
+					_ =>
+						return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed segment")),
+				}
+			},
+
+			Some("key") =>
+				match parts.next().and_then(|v| v.parse::<Key>().ok()) {
+					Some(key) =>
+						result.key = Some(key),
+
+					None =>
+						return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed key")),
+				},
+
+			_ =>
+				return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed line")),
+		}
+	}
+
+	Ok(result)
+}
+
+fn store(path: &Path, analysis: &Analysis, settings: &settings::Analyzer) -> io::Result<()> {
+	if let Some(parent) = path.parent() {
+		try!(fs::create_dir_all(parent));
+	}
+
+	let mut file = BufWriter::new(try!(File::create(path)));
+
+	for beat in &analysis.beats {
+		if let Some(index) = settings.beat().bands().iter().position(|b| b == beat.band()) {
+			let side = match beat.side() {
+				Side::Left   => "l",
+				Side::Center => "c",
+				Side::Right  => "r",
+			};
+
+			try!(writeln!(file, "beat {} {} {}", beat.offset(), index, side));
+		}
+	}
+
+	for segment in &analysis.segments {
+		try!(writeln!(file, "segment {} {} {} {} {}",
+			segment.start(), segment.end(), segment.intensity(), segment.label(),
+			segment.key().map(|k| k.to_string()).unwrap_or("-".to_owned())));
+	}
+
+	if let Some(key) = analysis.key {
+		try!(writeln!(file, "key {}", key));
+	}
+
+	Ok(())
+}
@@ -0,0 +1,177 @@
+use analyzer::spectrum::Spectrum;
+
+/// The lowest frequency considered for chroma and fundamental.
+const LOW: f64 = 55.0;
+
+/// The highest frequency considered for chroma.
+const HIGH: f64 = 5000.0;
+
+/// The highest fundamental looked for.
+const FUNDAMENTAL: f64 = 1000.0;
+
+/// How many harmonics the harmonic product spectrum multiplies.
+const HARMONICS: usize = 4;
+
+/// The pitch content of a window.
+#[derive(Clone, Debug)]
+pub struct Pitch {
+	offset:      f64,
+	chroma:      [f64; 12],
+	fundamental: f64,
+}
+
+impl Pitch {
+	pub fn new(offset: f64, spectrum: &Spectrum) -> Self {
+		Pitch {
+			offset:      offset,
+			chroma:      chroma(spectrum),
+			fundamental: fundamental(spectrum),
+		}
+	}
+
+	/// Moves the pitch in time.
+	pub fn shift(mut self, by: f64) -> Self {
+		self.offset += by;
+		self
+	}
+
+	#[inline(always)]
+	pub fn offset(&self) -> f64 {
+		self.offset
+	}
+
+	/// The energy of every pitch class starting from C, normalized so the
+	/// strongest is 1.0.
+	#[inline(always)]
+	pub fn chroma(&self) -> &[f64; 12] {
+		&self.chroma
+	}
+
+	/// The strongest pitch class, 0 is C and 11 is B.
+	pub fn class(&self) -> u8 {
+		let mut result = 0;
+
+		for (class, &energy) in self.chroma.iter().enumerate() {
+			if energy > self.chroma[result] {
+				result = class;
+			}
+		}
+
+		result as u8
+	}
+
+	/// The estimated fundamental in Hz, zero when there's none.
+	#[inline(always)]
+	pub fn fundamental(&self) -> f64 {
+		self.fundamental
+	}
+
+	/// The fundamental in semitones from A4, `None` when there's none.
+	pub fn semitones(&self) -> Option<f64> {
+		if self.fundamental > 0.0 {
+			Some(semitones(self.fundamental))
+		}
+		else {
+			None
+		}
+	}
+}
+
+/// Folds the spectrum energy on the twelve pitch classes.
+///
+/// Low bins are wider than a semitone, so the energy of every bin is spread
+/// over the semitones it covers instead of going all to the closest one.
+fn chroma(spectrum: &Spectrum) -> [f64; 12] {
+	let mut result = [0.0; 12];
+	let     width  = spectrum.frequency(1);
+
+	for bin in spectrum.bin(LOW) .. spectrum.bin(HIGH) + 1 {
+		let frequency = spectrum.frequency(bin);
+
+		if frequency < LOW {
+			continue;
+		}
+
+		let energy = spectrum.magnitude(bin).powi(2);
+		let low    = semitones(frequency - width / 2.0);
+		let high   = semitones(frequency + width / 2.0);
+
+		for semitone in low.round() as i64 .. high.round() as i64 + 1 {
+			let covered = (high.min(semitone as f64 + 0.5) - low.max(semitone as f64 - 0.5)) / (high - low);
+
+			// A4 is the tenth class starting from C.
+			let class = ((((semitone + 9) % 12) + 12) % 12) as usize;
+
+			if covered > 0.0 {
+				result[class] += energy * covered;
+			}
+		}
+	}
+
+	let max = result.iter().cloned().fold(0.0, f64::max);
+
+	if max > 0.0 {
+		for value in result.iter_mut() {
+			*value /= max;
+		}
+	}
+
+	result
+}
+
+/// Finds the fundamental with a harmonic product spectrum, summing the
+/// logarithms so it doesn't underflow.
+///
+/// The bins are too coarse for the low notes, so the peak is placed between
+/// its neighbours with a parabolic interpolation.
+fn fundamental(spectrum: &Spectrum) -> f64 {
+	let low  = spectrum.bin(LOW);
+	let high = spectrum.bin(FUNDAMENTAL);
+
+	let score = |bin: usize| (1 .. HARMONICS + 1).fold(0.0, |acc, h|
+		acc + (spectrum.magnitude(bin * h) + 1e-9).ln());
+
+	let mut best = None;
+	let mut peak = ::std::f64::NEG_INFINITY;
+
+	for bin in low .. high + 1 {
+		if bin * HARMONICS >= spectrum.len() {
+			break;
+		}
+
+		let value = score(bin);
+
+		if value > peak {
+			peak = value;
+			best = Some(bin);
+		}
+	}
+
+	match best {
+		Some(bin) if spectrum.magnitude(bin) > 1e-6 => {
+			if bin == 0 || (bin + 1) * HARMONICS >= spectrum.len() {
+				return spectrum.frequency(bin);
+			}
+
+			let (a, b, c) = (score(bin - 1), peak, score(bin + 1));
+			let curve     = a - 2.0 * b + c;
+
+			let offset = if curve < 0.0 {
+				0.5 * (a - c) / curve
+			}
+			else {
+				0.0
+			};
+
+			spectrum.frequency(bin) + offset * spectrum.frequency(1)
+		},
+
+		_ =>
+			0.0,
+	}
+}
+
+/// The frequency in semitones from A4.
+fn semitones(frequency: f64) -> f64 {
+	12.0 * (frequency / 440.0).log2()
+}
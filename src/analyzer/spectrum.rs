@@ -0,0 +1,198 @@
+use std::f64::consts::PI;
+use std::ops::Range;
+
+use num::Complex;
+
+use settings::analyzer::Filter;
+use analyzer::filter;
+
+/// Splits the incoming samples in overlapping windows, mixed down to mono.
+#[derive(Debug)]
+pub struct Samples {
+	size: usize,
+	hop:  usize,
+	rate: u32,
+
+	buffer: Vec<f64>,
+	offset: u64,
+}
+
+impl Samples {
+	pub fn new(size: usize, hop: usize, rate: u32) -> Self {
+		Samples {
+			size: size,
+			hop:  hop,
+			rate: rate,
+
+			buffer: Vec::with_capacity(size * 2),
+			offset: 0,
+		}
+	}
+
+	/// Pushes packed stereo samples.
+	pub fn push(&mut self, samples: &[i16]) {
+		for pair in samples.chunks(2) {
+			let sum = pair.iter().fold(0.0, |acc, &s| acc + s as f64 / 32768.0);
+
+			self.buffer.push(sum / pair.len() as f64);
+		}
+	}
+
+	/// The next window and the offset in seconds of its start, if there are
+	/// enough samples.
+	pub fn next(&mut self) -> Option<(f64, Vec<f64>)> {
+		if self.buffer.len() < self.size {
+			return None;
+		}
+
+		let offset = self.offset as f64 / self.rate as f64;
+		let window = self.buffer[.. self.size].to_vec();
+
+		self.buffer.drain(.. self.hop);
+		self.offset += self.hop as u64;
+
+		Some((offset, window))
+	}
+}
+
+/// The spectrum of a window of samples, only the bins up to the Nyquist
+/// frequency are kept.
+#[derive(Clone, Debug)]
+pub struct Spectrum {
+	bins: Vec<Complex<f64>>,
+	rate: u32,
+	size: usize,
+}
+
+impl Spectrum {
+	/// Computes the spectrum of the samples with the given window filter, zero
+	/// padding them to a power of two.
+	pub fn new(samples: &[f64], rate: u32, filter: Filter) -> Self {
+		let size       = samples.len().next_power_of_two();
+		let mut buffer = vec![Complex::new(0.0, 0.0); size];
+
+		for (i, &sample) in samples.iter().enumerate() {
+			buffer[i] = Complex::new(sample * filter::coefficient(filter, i, samples.len()), 0.0);
+		}
+
+		fft(&mut buffer);
+		buffer.truncate(size / 2 + 1);
+
+		Spectrum {
+			bins: buffer,
+			rate: rate,
+			size: size,
+		}
+	}
+
+	#[inline(always)]
+	pub fn bins(&self) -> &[Complex<f64>] {
+		&self.bins
+	}
+
+	#[inline(always)]
+	pub fn len(&self) -> usize {
+		self.bins.len()
+	}
+
+	#[inline(always)]
+	pub fn magnitude(&self, bin: usize) -> f64 {
+		self.bins[bin].norm()
+	}
+
+	/// The center frequency of the given bin.
+	pub fn frequency(&self, bin: usize) -> f64 {
+		bin as f64 * self.rate as f64 / self.size as f64
+	}
+
+	/// The bin containing the given frequency.
+	pub fn bin(&self, frequency: f64) -> usize {
+		let bin = (frequency * self.size as f64 / self.rate as f64).round() as usize;
+
+		if bin >= self.bins.len() {
+			self.bins.len() - 1
+		}
+		else {
+			bin
+		}
+	}
+
+	/// The energy between the given frequencies.
+	pub fn energy(&self, range: &Range<u32>) -> f64 {
+		let low  = self.bin(range.start as f64);
+		let high = self.bin(range.end as f64);
+
+		self.bins[low .. high + 1].iter().fold(0.0, |acc, b| acc + b.norm_sqr()) / self.size as f64
+	}
+
+	/// The magnitude weighted mean frequency.
+	pub fn centroid(&self) -> f64 {
+		let mut weighted = 0.0;
+		let mut total    = 0.0;
+
+		for bin in 0 .. self.bins.len() {
+			let magnitude = self.magnitude(bin);
+
+			weighted += self.frequency(bin) * magnitude;
+			total    += magnitude;
+		}
+
+		if total > 0.0 {
+			weighted / total
+		}
+		else {
+			0.0
+		}
+	}
+}
+
+/// An in place iterative radix-2 FFT, the length must be a power of two.
+pub fn fft(buffer: &mut [Complex<f64>]) {
+	let n = buffer.len();
+
+	if n < 2 {
+		return;
+	}
+
+	// Reorder by bit reversed indices.
+	let mut j = 0;
+
+	for i in 1 .. n {
+		let mut bit = n >> 1;
+
+		while j & bit != 0 {
+			j   ^= bit;
+			bit >>= 1;
+		}
+
+		j |= bit;
+
+		if i < j {
+			buffer.swap(i, j);
+		}
+	}
+
+	// Combine the butterflies.
+	let mut length = 2;
+
+	while length <= n {
+		let angle = -2.0 * PI / length as f64;
+		let step  = Complex::new(angle.cos(), angle.sin());
+
+		for start in (0 .. n).filter(|i| i % length == 0) {
+			let mut twiddle = Complex::new(1.0, 0.0);
+
+			for k in 0 .. length / 2 {
+				let a = buffer[start + k];
+				let b = buffer[start + k + length / 2] * twiddle;
+
+				buffer[start + k]              = a + b;
+				buffer[start + k + length / 2] = a - b;
+
+				twiddle = twiddle * step;
+			}
+		}
+
+		length <<= 1;
+	}
+}
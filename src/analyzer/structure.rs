@@ -0,0 +1,234 @@
+use analyzer::{Envelope, Key};
+
+/// How many seconds of envelopes are averaged together before comparing them.
+const BLOCK: f64 = 0.5;
+
+/// Half the size in blocks of the checkerboard kernel, about eight seconds.
+const KERNEL: usize = 16;
+
+/// The shortest segment in seconds.
+const MINIMUM: f64 = 8.0;
+
+/// The labels segments can get.
+pub const LABELS: [&'static str; 6] = ["intro", "verse", "chorus", "drop", "breakdown", "outro"];
+
+/// A section of the song.
+#[derive(Clone, Debug)]
+pub struct Segment {
+	start:     f64,
+	end:       f64,
+	intensity: f64,
+	label:     String,
+	key:       Option<Key>,
+}
+
+impl Segment {
+	pub fn new(start: f64, end: f64, intensity: f64, label: &str) -> Self {
+		Segment {
+			start:     start,
+			end:       end,
+			intensity: intensity,
+			label:     label.to_owned(),
+			key:       None,
+		}
+	}
+
+	pub fn with_key(mut self, key: Option<Key>) -> Self {
+		self.key = key;
+		self
+	}
+
+	#[inline(always)]
+	pub fn start(&self) -> f64 {
+		self.start
+	}
+
+	#[inline(always)]
+	pub fn end(&self) -> f64 {
+		self.end
+	}
+
+	/// How intense the segment is compared to the rest of the song, between
+	/// 0.0 and 1.0.
+	#[inline(always)]
+	pub fn intensity(&self) -> f64 {
+		self.intensity
+	}
+
+	/// One of `intro`, `verse`, `chorus`, `drop`, `breakdown` and `outro`.
+	#[inline(always)]
+	pub fn label(&self) -> &str {
+		&self.label
+	}
+
+	/// The key of the segment, if it has been detected.
+	#[inline(always)]
+	pub fn key(&self) -> Option<Key> {
+		self.key
+	}
+
+	pub fn contains(&self, time: f64) -> bool {
+		time >= self.start && time < self.end
+	}
+}
+
+/// Splits the song in segments from the envelopes of the whole song.
+///
+/// The boundaries are the peaks of the novelty from a checkerboard kernel
+/// run along the diagonal of the self-similarity of the envelopes, the
+/// segments are then labeled by their loudness compared to each other.
+pub fn segments(envelopes: &[Envelope]) -> Vec<Segment> {
+	let blocks = blocks(envelopes);
+
+	if blocks.len() < KERNEL * 2 {
+		return Vec::new();
+	}
+
+	let novelty = novelty(&blocks);
+	let mut boundaries = vec![0];
+
+	for index in peaks(&novelty) {
+		if (index - boundaries[boundaries.len() - 1]) as f64 * BLOCK >= MINIMUM &&
+		   (blocks.len() - index) as f64 * BLOCK >= MINIMUM
+		{
+			boundaries.push(index);
+		}
+	}
+
+	boundaries.push(blocks.len());
+
+	// The loudness of every segment.
+	let loudness = boundaries.windows(2).map(|w|
+		blocks[w[0] .. w[1]].iter().fold(0.0, |acc, b| acc + b[0]) / (w[1] - w[0]) as f64
+	).collect::<Vec<f64>>();
+
+	let min = loudness.iter().cloned().fold(::std::f64::INFINITY, f64::min);
+	let max = loudness.iter().cloned().fold(0.0, f64::max);
+
+	let mut result = Vec::new();
+
+	for (index, window) in boundaries.windows(2).enumerate() {
+		let intensity = if max > min {
+			(loudness[index] - min) / (max - min)
+		}
+		else {
+			0.5
+		};
+
+		let first = index == 0;
+		let last  = index == loudness.len() - 1;
+
+		let label = if intensity >= 0.85 {
+			"drop"
+		}
+		else if intensity >= 0.55 {
+			"chorus"
+		}
+		else if first && loudness.len() > 1 {
+			"intro"
+		}
+		else if last && loudness.len() > 1 {
+			"outro"
+		}
+		else if intensity >= 0.25 {
+			"verse"
+		}
+		else {
+			"breakdown"
+		};
+
+		result.push(Segment::new(window[0] as f64 * BLOCK, window[1] as f64 * BLOCK, intensity, label));
+	}
+
+	result
+}
+
+/// Averages the features of the envelopes in blocks, the first feature is
+/// always the loudness.
+fn blocks(envelopes: &[Envelope]) -> Vec<Vec<f64>> {
+	let mut result: Vec<Vec<f64>> = Vec::new();
+	let mut counts = Vec::new();
+
+	for envelope in envelopes {
+		let index = (envelope.offset() / BLOCK) as usize;
+
+		while result.len() <= index {
+			result.push(vec![0.0; envelope.bands().len() + 2]);
+			counts.push(0);
+		}
+
+		let block = &mut result[index];
+
+		block[0] += envelope.rms();
+		block[1] += envelope.centroid() / 22050.0;
+
+		for (value, energy) in block[2 ..].iter_mut().zip(envelope.bands()) {
+			*value += (1.0 + energy).ln();
+		}
+
+		counts[index] += 1;
+	}
+
+	for (block, &count) in result.iter_mut().zip(counts.iter()) {
+		if count > 0 {
+			for value in block.iter_mut() {
+				*value /= count as f64;
+			}
+		}
+	}
+
+	result
+}
+
+/// The cosine similarity between two blocks.
+fn similarity(a: &[f64], b: &[f64]) -> f64 {
+	let dot = a.iter().zip(b).fold(0.0, |acc, (x, y)| acc + x * y);
+	let na  = a.iter().fold(0.0, |acc, x| acc + x * x).sqrt();
+	let nb  = b.iter().fold(0.0, |acc, x| acc + x * x).sqrt();
+
+	if na == 0.0 || nb == 0.0 {
+		0.0
+	}
+	else {
+		dot / (na * nb)
+	}
+}
+
+/// The novelty of every block, high where the past and the future of the
+/// block are similar among themselves but not to each other.
+fn novelty(blocks: &[Vec<f64>]) -> Vec<f64> {
+	let mut result = vec![0.0; blocks.len()];
+
+	for center in KERNEL .. blocks.len() - KERNEL {
+		let mut value = 0.0;
+
+		for i in center - KERNEL .. center + KERNEL {
+			for j in center - KERNEL .. center + KERNEL {
+				let sign = if (i < center) == (j < center) { 1.0 } else { -1.0 };
+
+				value += sign * similarity(&blocks[i], &blocks[j]);
+			}
+		}
+
+		result[center] = value;
+	}
+
+	result
+}
+
+/// The local maxima of the novelty above its mean.
+fn peaks(novelty: &[f64]) -> Vec<usize> {
+	let mean = novelty.iter().fold(0.0, |acc, v| acc + v) / novelty.len() as f64;
+	let mut result = Vec::new();
+
+	for index in 1 .. novelty.len() - 1 {
+		let from = if index > KERNEL { index - KERNEL } else { 0 };
+		let to   = if index + KERNEL < novelty.len() { index + KERNEL } else { novelty.len() };
+
+		if novelty[index] > mean && novelty[from .. to].iter().all(|&v| v <= novelty[index]) {
+			result.push(index);
+		}
+	}
+
+	result
+}
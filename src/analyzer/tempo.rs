@@ -0,0 +1,193 @@
+use std::cmp;
+use std::f64::consts::PI;
+
+use util::Ring;
+use settings::analyzer as settings;
+
+/// Estimates the tempo and the phase of the beat grid from the onsets.
+///
+/// The tempo comes from a histogram of the intervals between onsets, folded
+/// in the configured range, the phase is the circular mean of the onsets on
+/// the resulting period.
+///
+/// When the tempo map is known, from a MIDI file for instance, it's used
+/// instead.
+#[derive(Debug)]
+pub struct Tempo {
+	settings: settings::Tempo,
+	onsets:   Ring<f64>,
+	map:      Vec<(f64, f64)>,
+
+	period: Option<f64>,
+	phase:  f64,
+}
+
+impl Tempo {
+	pub fn new(settings: &settings::Tempo) -> Self {
+		Tempo {
+			settings: settings.clone(),
+			onsets:   Ring::new(settings.history()),
+			map:      Vec::new(),
+
+			period: None,
+			phase:  0.0,
+		}
+	}
+
+	/// Uses the given tempo map instead of estimating the tempo, every entry is
+	/// the time a tempo starts at, on a beat, and the length of its beats.
+	pub fn fix(&mut self, map: Vec<(f64, f64)>) {
+		self.map = map;
+
+		if let Some(&(start, period)) = self.map.first() {
+			self.period = Some(period);
+			self.phase  = start;
+		}
+	}
+
+	/// Adds an onset and estimates the tempo again.
+	pub fn handle(&mut self, offset: f64) {
+		// The tempo is known, it only has to follow the map.
+		if let Some((start, period)) = self.at(offset) {
+			self.period = Some(period);
+			self.phase  = start;
+
+			return;
+		}
+
+		if let Some(&last) = self.onsets.back() {
+			if offset <= last {
+				return;
+			}
+		}
+
+		self.onsets.push(offset);
+		self.estimate();
+	}
+
+	/// The estimated tempo in beats per minute.
+	pub fn bpm(&self) -> Option<f64> {
+		self.period.map(|p| 60.0 / p)
+	}
+
+	/// The estimated length of a beat in seconds.
+	pub fn period(&self) -> Option<f64> {
+		self.period
+	}
+
+	/// The first beat of the grid after the given time, divided in the given
+	/// subdivisions.
+	pub fn next(&self, time: f64, subdivision: u32) -> Option<f64> {
+		let phase = self.at(time).map(|(s, _)| s).unwrap_or(self.phase);
+
+		self.step(time, subdivision).map(|step|
+			phase + (((time - phase) / step).floor() + 1.0) * step)
+	}
+
+	/// The beat of the grid closest to the given time, divided in the given
+	/// subdivisions.
+	pub fn quantize(&self, time: f64, subdivision: u32) -> Option<f64> {
+		let phase = self.at(time).map(|(s, _)| s).unwrap_or(self.phase);
+
+		self.step(time, subdivision).map(|step|
+			phase + ((time - phase) / step).round() * step)
+	}
+
+	fn step(&self, time: f64, subdivision: u32) -> Option<f64> {
+		self.at(time).map(|(_, p)| p).or(self.period)
+			.map(|p| p / cmp::max(subdivision, 1) as f64)
+	}
+
+	/// The entry of the tempo map in effect at the given time.
+	fn at(&self, time: f64) -> Option<(f64, f64)> {
+		self.map.iter().rev().find(|&&(s, _)| s <= time)
+			.or(self.map.first()).cloned()
+	}
+
+	fn estimate(&mut self) {
+		if self.onsets.len() < 4 {
+			return;
+		}
+
+		let min  = self.settings.min();
+		let max  = self.settings.max();
+		let bins = (max - min).ceil() as usize + 1;
+
+		let mut histogram = vec![0.0; bins];
+
+		for (i, a) in self.onsets.iter().enumerate() {
+			for (j, b) in self.onsets.iter().enumerate().skip(i + 1) {
+				let interval = b - a;
+
+				// Intervals longer than a few beats say little about the tempo.
+				if interval > 4.0 * 60.0 / min {
+					break;
+				}
+
+				if let Some(bpm) = fold(60.0 / interval, min, max) {
+					// Closer onsets are more likely to be next to each other on the
+					// grid.
+					histogram[(bpm - min).round() as usize] += 1.0 / (j - i) as f64;
+				}
+			}
+		}
+
+		// Smooth the histogram a little so close tempos help each other out.
+		let smooth = (0 .. bins).map(|i| {
+			let before = if i > 0 { histogram[i - 1] } else { 0.0 };
+			let after  = if i + 1 < bins { histogram[i + 1] } else { 0.0 };
+
+			before * 0.5 + histogram[i] + after * 0.5
+		}).collect::<Vec<f64>>();
+
+		let (index, weight) = smooth.iter().enumerate()
+			.fold((0, 0.0), |(bi, bw), (i, &w)| if w > bw { (i, w) } else { (bi, bw) });
+
+		if weight == 0.0 {
+			return;
+		}
+
+		let period = 60.0 / (min + index as f64);
+
+		// The phase is the circular mean of the onsets on the period.
+		let (mut x, mut y) = (0.0, 0.0);
+
+		for onset in self.onsets.iter() {
+			let angle = 2.0 * PI * (onset % period) / period;
+
+			x += angle.cos();
+			y += angle.sin();
+		}
+
+		let mut phase = y.atan2(x) / (2.0 * PI) * period;
+
+		if phase < 0.0 {
+			phase += period;
+		}
+
+		self.period = Some(period);
+		self.phase  = phase;
+	}
+}
+
+/// Folds the tempo by octaves until it's in range.
+fn fold(mut bpm: f64, min: f64, max: f64) -> Option<f64> {
+	if !bpm.is_finite() || bpm <= 0.0 {
+		return None;
+	}
+
+	while bpm < min {
+		bpm *= 2.0;
+	}
+
+	while bpm > max {
+		bpm /= 2.0;
+	}
+
+	if bpm < min {
+		None
+	}
+	else {
+		Some(bpm)
+	}
+}
@@ -0,0 +1,120 @@
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use glium::{Display, Surface};
+use glium::glutin::Event;
+use glium::glutin::ElementState::Pressed;
+use glium::glutin::VirtualKeyCode::{Escape, Space};
+
+use util::Clock;
+use util::clock::Audio;
+use sound::Sound;
+
+/// The rate of the click track.
+const RATE: u32 = 44100;
+
+/// Seconds of silence before the first click.
+const LEAD: f64 = 1.0;
+
+/// Seconds between clicks, 120 beats per minute.
+const INTERVAL: f64 = 0.5;
+
+/// How many clicks are played.
+const CLICKS: usize = 24;
+
+/// How many clicks are left out of the measure while the player gets into
+/// the rhythm.
+const SKIP: usize = 4;
+
+/// Seconds a click lasts.
+const CLICK: f64 = 0.03;
+
+/// Plays the click track and collects the taps on space, returning how late
+/// the taps are on average, or `None` if the calibration has been cancelled
+/// or there have been no taps.
+///
+/// The screen stays blank so the taps only follow what's heard, and the clock
+/// follows the playback position so the taps are compared to the clicks as
+/// they're played, not as they're queued.
+pub fn run<C: Clock>(display: &Display, sound: &mut Sound, clock: &Audio<C>) -> Option<f64> {
+	let queued = clock.now();
+	sound.queue(1, &track(), RATE);
+
+	let start = queued + LEAD;
+	let end   = start + CLICKS as f64 * INTERVAL + 1.0;
+
+	let mut offsets = Vec::new();
+
+	loop {
+		if let Some(position) = sound.position() {
+			clock.update(queued + position);
+		}
+
+		if clock.now() >= end {
+			break;
+		}
+
+		for event in display.poll_events() {
+			match event {
+				Event::Closed | Event::KeyboardInput(_, _, Some(Escape)) =>
+					return None,
+
+				Event::KeyboardInput(Pressed, _, Some(Space)) => {
+					let at    = clock.now() - start;
+					let index = (at / INTERVAL).round();
+
+					if index >= SKIP as f64 && index < CLICKS as f64 {
+						offsets.push(at - index * INTERVAL);
+					}
+				},
+
+				_ =>
+					(),
+			}
+		}
+
+		let mut target = display.draw();
+		target.clear_color(0.0, 0.0, 0.0, 1.0);
+		let _ = target.finish();
+	}
+
+	if offsets.is_empty() {
+		None
+	}
+	else {
+		Some(offsets.iter().fold(0.0, |acc, o| acc + o) / offsets.len() as f64)
+	}
+}
+
+/// Writes the latency as settings to merge with the others.
+pub fn store(path: &Path, latency: f64) -> io::Result<()> {
+	let mut file = try!(File::create(path));
+
+	try!(writeln!(file, "[audio]"));
+	try!(writeln!(file, "latency = {:.3}", latency));
+
+	Ok(())
+}
+
+/// Synthesizes the click track, short decaying sine blips with the first of
+/// every four higher.
+fn track() -> Vec<i16> {
+	let length = ((LEAD + CLICKS as f64 * INTERVAL) * RATE as f64) as usize;
+	let mut result = vec![0i16; length];
+
+	for index in 0 .. CLICKS {
+		let frequency = if index % 4 == 0 { 1760.0 } else { 880.0 };
+		let offset    = ((LEAD + index as f64 * INTERVAL) * RATE as f64) as usize;
+
+		for i in 0 .. (CLICK * RATE as f64) as usize {
+			let time  = i as f64 / RATE as f64;
+			let value = (2.0 * PI * frequency * time).sin() * (-time / CLICK * 5.0).exp();
+
+			result[offset + i] = (value * 0.8 * ::std::i16::MAX as f64) as i16;
+		}
+	}
+
+	result
+}
@@ -0,0 +1,103 @@
+use na::{Iso2, Vec1, Vec2};
+use nc::shape::{Ball, Cuboid};
+use nc::geometry;
+
+use util::deg;
+use game::{Ship, Projectile};
+use game::ship;
+use game::projectile::{Plasma, Ray};
+
+/// The size of a ship with a scale of 1.0, it's the same the renderer uses.
+const SHIP: f32 = 12.5;
+
+#[derive(Clone, Debug)]
+pub enum Shape {
+	Ball(Ball<f32>),
+	Cuboid(Cuboid<Vec2<f32>>),
+}
+
+/// A collision body on the playfield plane, the depth is ignored.
+#[derive(Clone, Debug)]
+pub struct Body {
+	transform: Iso2<f32>,
+	shape:     Shape,
+}
+
+impl Body {
+	pub fn new(x: f32, y: f32, angle: f32, shape: Shape) -> Self {
+		Body {
+			transform: Iso2::new(Vec2::new(x, y), Vec1::new(angle)),
+			shape:     shape,
+		}
+	}
+
+	/// The area around a ship where projectiles count as grazing.
+	pub fn graze(ship: &Ship) -> Self {
+		Body::new(ship.position.x, ship.position.y, 0.0,
+			Shape::Ball(Ball::new(SHIP * ship.scale * 2.5)))
+	}
+
+	pub fn collides(&self, other: &Body) -> bool {
+		let (a, b) = (&self.transform, &other.transform);
+
+		match (&self.shape, &other.shape) {
+			(&Shape::Ball(ref sa), &Shape::Ball(ref sb)) =>
+				geometry::contact(a, sa, b, sb, 0.0).is_some(),
+
+			(&Shape::Ball(ref sa), &Shape::Cuboid(ref sb)) =>
+				geometry::contact(a, sa, b, sb, 0.0).is_some(),
+
+			(&Shape::Cuboid(ref sa), &Shape::Ball(ref sb)) =>
+				geometry::contact(a, sa, b, sb, 0.0).is_some(),
+
+			(&Shape::Cuboid(ref sa), &Shape::Cuboid(ref sb)) =>
+				geometry::contact(a, sa, b, sb, 0.0).is_some(),
+		}
+	}
+}
+
+impl<'a> From<&'a Ship> for Body {
+	fn from(ship: &'a Ship) -> Body {
+		let size = SHIP * ship.scale;
+
+		// Only the rotation on the playfield plane is taken into account, the
+		// shapes are approximated by what they look like from above.
+		let shape = match ship.shape {
+			ship::Shape::Cube =>
+				Shape::Cuboid(Cuboid::new(Vec2::new(size, size))),
+
+			ship::Shape::Tetrahedron =>
+				Shape::Ball(Ball::new(size * 0.8)),
+
+			ship::Shape::Octahedron =>
+				Shape::Ball(Ball::new(size)),
+		};
+
+		Body::new(ship.position.x, ship.position.y, -deg(ship.orientation.yaw), shape)
+	}
+}
+
+impl<'a> From<&'a Projectile> for Body {
+	fn from(projectile: &'a Projectile) -> Body {
+		match projectile {
+			&Projectile::Plasma(Plasma::Static { position, radius, .. }) |
+			&Projectile::Plasma(Plasma::Dynamic { position, radius, .. }) =>
+				// The plasma is drawn on a quad twice the size of the radius.
+				Body::new(position.x, position.y, 0.0, Shape::Ball(Ball::new(radius * 2.0))),
+
+			&Projectile::Ray(Ray::Static { position, orientation, width, .. }) |
+			&Projectile::Ray(Ray::Dynamic { position, orientation, width, .. }) => {
+				// The ray starts at its position and extends for a thousand times its
+				// width behind it, so the center is halfway through.
+				let angle  = -deg(orientation.yaw);
+				let length = 1000.0 * width;
+
+				Body::new(
+					position.x - angle.cos() * length / 2.0,
+					position.y - angle.sin() * length / 2.0,
+					angle,
+					Shape::Cuboid(Cuboid::new(Vec2::new(length / 2.0, width))))
+			}
+		}
+	}
+}
@@ -0,0 +1,257 @@
+use std::ops::{Deref, DerefMut};
+use std::f32::consts::PI;
+
+use util::{Aspect, Color};
+use game::{Update, Alive, CanDamage, Support, Ship, Player, Position, Velocity, Projectile};
+use game::projectile::{Owner, Plasma};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Path {
+	/// Goes straight down the playfield.
+	Straight,
+
+	/// Goes down the playfield waving around the entry point.
+	Sine {
+		amplitude: f32,
+		frequency: f32,
+	},
+
+	/// Aims at where the player was when spawned and keeps going.
+	Dive,
+
+	/// Crosses the playfield from one side to the other, slowly descending.
+	Sweep,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Fire {
+	/// Never fires.
+	None,
+
+	/// Fires straight down.
+	Down {
+		rate: f64,
+	},
+
+	/// Fires towards the player.
+	Aimed {
+		rate: f64,
+	},
+
+	/// Fires the named pattern.
+	Pattern {
+		name: String,
+		rate: f64,
+	},
+}
+
+impl Fire {
+	/// Makes the fire faster by the given factor.
+	pub fn faster(self, factor: f64) -> Fire {
+		match self {
+			Fire::None =>
+				Fire::None,
+
+			Fire::Down { rate } =>
+				Fire::Down { rate: rate / factor },
+
+			Fire::Aimed { rate } =>
+				Fire::Aimed { rate: rate / factor },
+
+			Fire::Pattern { name, rate } =>
+				Fire::Pattern { name: name, rate: rate / factor },
+		}
+	}
+}
+
+/// What an enemy fired.
+#[derive(Debug)]
+pub enum Shot {
+	Projectile(Projectile),
+	Pattern(String),
+}
+
+#[derive(Debug)]
+pub struct Enemy {
+	ship: Ship,
+
+	path:  Path,
+	fire:  Fire,
+	speed: f32,
+	color: Color,
+
+	origin:  Position,
+	heading: Velocity,
+	spawned: f64,
+	fired:   f64,
+}
+
+impl Enemy {
+	pub fn new(ship: Ship, path: Path, fire: Fire, speed: f32, color: Color, time: f64) -> Self {
+		Enemy {
+			origin:  ship.position,
+			heading: Default::default(),
+			spawned: time,
+			fired:   time,
+
+			ship:  ship,
+			path:  path,
+			fire:  fire,
+			speed: speed,
+			color: color,
+		}
+	}
+
+	/// Points the enemy towards the given position, used by the paths that need
+	/// to know where they're going.
+	pub fn aim(&mut self, target: Position) {
+		let x = target.x - self.position.x;
+		let y = target.y - self.position.y;
+		let l = (x * x + y * y).sqrt();
+
+		if l > 0.0 {
+			self.heading.x = x / l;
+			self.heading.y = y / l;
+		}
+		else {
+			self.heading.y = 1.0;
+		}
+	}
+
+	pub fn path(&self) -> Path {
+		self.path
+	}
+
+	pub fn spawned(&self) -> f64 {
+		self.spawned
+	}
+
+	pub fn color(&self) -> Color {
+		self.color
+	}
+
+	/// Holds the fire until the given time, so the first shot lands on it.
+	pub fn arm(&mut self, time: f64) {
+		match self.fire {
+			Fire::None =>
+				(),
+
+			Fire::Down { rate } | Fire::Aimed { rate } | Fire::Pattern { rate, .. } =>
+				self.fired = time - rate,
+		}
+	}
+
+	/// Fires if the rate allows it, returning what has been fired.
+	pub fn fire(&mut self, support: &Support, target: Position) -> Option<Shot> {
+		let rate = match self.fire {
+			Fire::None =>
+				return None,
+
+			Fire::Down { rate } | Fire::Aimed { rate } | Fire::Pattern { rate, .. } =>
+				rate,
+		};
+
+		if support.time() - self.fired < rate {
+			return None;
+		}
+
+		self.fired = support.time();
+
+		let velocity = match self.fire {
+			Fire::Pattern { ref name, .. } =>
+				return Some(Shot::Pattern(name.clone())),
+
+			Fire::Aimed { .. } => {
+				let x = target.x - self.position.x;
+				let y = target.y - self.position.y;
+				let l = (x * x + y * y).sqrt();
+
+				if l == 0.0 {
+					return None;
+				}
+
+				Velocity { x: 2.0 * x / l, y: 2.0 * y / l, .. Default::default() }
+			},
+
+			_ =>
+				Velocity { y: 2.0, .. Default::default() },
+		};
+
+		Some(Shot::Projectile(Projectile::Plasma(Plasma::Static {
+			owner: Owner::Enemy,
+
+			radius: 1.5,
+			border: self.color,
+
+			position: self.position,
+			velocity: velocity,
+		})))
+	}
+}
+
+impl Deref for Enemy {
+	type Target = Ship;
+
+	fn deref(&self) -> &Ship {
+		&self.ship
+	}
+}
+
+impl DerefMut for Enemy {
+	fn deref_mut(&mut self) -> &mut Ship {
+		&mut self.ship
+	}
+}
+
+impl Update for Enemy {
+	fn update(&mut self, support: &Support) {
+		let elapsed = (support.time() - self.spawned) as f32;
+
+		match self.path {
+			Path::Straight => {
+				self.ship.position.y += self.speed;
+			},
+
+			Path::Sine { amplitude, frequency } => {
+				self.ship.position.y += self.speed;
+				self.ship.position.x  = self.origin.x + amplitude * (2.0 * PI * frequency * elapsed).sin();
+			},
+
+			Path::Dive => {
+				self.ship.position.x += self.heading.x * self.speed;
+				self.ship.position.y += self.heading.y * self.speed;
+			},
+
+			Path::Sweep => {
+				let direction = if self.origin.x <= 0.0 { 1.0 } else { -1.0 };
+
+				self.ship.position.x += direction * self.speed;
+				self.ship.position.y += self.speed / 4.0;
+			},
+		}
+
+		self.ship.orientation.roll  = (self.ship.orientation.roll  + self.ship.velocity.roll)  % 360.0;
+		self.ship.orientation.pitch = (self.ship.orientation.pitch + self.ship.velocity.pitch) % 360.0;
+		self.ship.orientation.yaw   = (self.ship.orientation.yaw   + self.ship.velocity.yaw)   % 360.0;
+	}
+}
+
+impl Alive for Enemy {
+	fn alive(&self, support: &Support) -> bool {
+		let width  = support.aspect().width() as f32;
+		let height = support.aspect().height() as f32;
+
+		// Enemies spawn on the edges, so give them some room before considering
+		// them gone.
+		let margin = 25.0 * self.scale;
+
+		self.position.x >= -margin && self.position.x <= width + margin &&
+		self.position.y >= -margin && self.position.y <= height + margin
+	}
+}
+
+impl CanDamage<Enemy, Player> for Enemy {
+	fn can_damage(enemy: &Enemy, player: &Player) -> bool {
+		player.is_vulnerable()
+	}
+}
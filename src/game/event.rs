@@ -0,0 +1,27 @@
+use util::Color;
+use game::Position;
+
+/// Things that happened during a tick.
+#[derive(Clone, Debug)]
+pub enum Event {
+	/// The player has been hit.
+	Hit {
+		position: Position,
+	},
+
+	/// A projectile flew close to the player without hitting.
+	Graze {
+		position: Position,
+	},
+
+	/// An enemy has been destroyed.
+	Destroyed {
+		position: Position,
+		color:    Color,
+	},
+
+	/// A new bar started, good for the bigger changes.
+	Bar {
+		index: u32,
+	},
+}
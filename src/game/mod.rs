@@ -13,12 +13,27 @@ pub use self::velocity::Velocity;
 mod state;
 pub use self::state::State;
 
-mod player;
+pub mod player;
 pub use self::player::Player;
 
 pub mod ship;
 pub use self::ship::Ship;
 
+pub mod enemy;
+pub use self::enemy::Enemy;
+
+mod spawner;
+pub use self::spawner::Spawner;
+
+mod score;
+pub use self::score::Score;
+
+mod weapon;
+pub use self::weapon::Weapon;
+
+pub mod pattern;
+pub use self::pattern::Runner;
+
 pub mod projectile;
 pub use self::projectile::Projectile;
 
@@ -27,3 +42,9 @@ pub use self::particle::Particle;
 
 mod traits;
 pub use self::traits::{Update, Alive, CanDamage};
+
+mod event;
+pub use self::event::Event;
+
+pub mod collision;
+pub use self::collision::Body;
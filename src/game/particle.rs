@@ -1,3 +1,5 @@
+use std::f32::consts::PI;
+
 use util::Aspect;
 use game::{Update, Alive, Support, Position, Velocity};
 
@@ -10,6 +12,21 @@ pub enum Particle {
 	}
 }
 
+impl Particle {
+	/// Creates a ring of dots flying away from the given position.
+	pub fn explosion(position: Position, count: usize) -> Vec<Particle> {
+		(0 .. count).map(|index| {
+			let angle = 2.0 * PI * index as f32 / count as f32;
+
+			Particle::Dot {
+				position: position,
+				velocity: Velocity { x: angle.cos() * 2.0, y: angle.sin() * 2.0, .. Default::default() },
+				scale:    1.0,
+			}
+		}).collect()
+	}
+}
+
 impl Update for Particle {
 	fn update(&mut self, support: &Support) {
 		#[inline(always)]
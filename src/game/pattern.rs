@@ -0,0 +1,231 @@
+use util::{deg, Aspect, Color};
+use settings;
+use settings::pattern::{Pattern, Kind, Child};
+use game::{Support, Position, Velocity, Projectile};
+use game::projectile::{Owner, Plasma};
+
+/// How deep repeats can nest.
+const DEPTH: u8 = 8;
+
+/// How many patterns can be running at once, so nested repeats can't flood a
+/// single tick.
+const EMITTERS: usize = 1024;
+
+#[derive(Debug)]
+struct Emitter {
+	pattern: Pattern,
+	origin:  Position,
+	color:   Color,
+	rotate:  f32,
+	depth:   u8,
+
+	start: f64,
+	step:  u32,
+}
+
+impl Emitter {
+	/// How many steps the pattern has and the delay between them.
+	fn steps(&self) -> (u32, f64) {
+		match self.pattern.kind() {
+			&Kind::Fan { .. } | &Kind::Ring { .. } =>
+				(1, 0.0),
+
+			&Kind::Spiral { count, delay, .. } |
+			&Kind::Aimed { count, delay } |
+			&Kind::Repeat { count, delay, .. } =>
+				(count, delay),
+		}
+	}
+
+	fn is_done(&self) -> bool {
+		self.step >= self.steps().0
+	}
+
+	fn shot(&self, angle: f32) -> Projectile {
+		let angle = deg(angle);
+
+		Projectile::Plasma(Plasma::Static {
+			owner: Owner::Enemy,
+
+			radius: self.pattern.size(),
+			border: self.pattern.color().unwrap_or(self.color),
+
+			position: self.origin,
+			velocity: Velocity {
+				x: angle.cos() * self.pattern.speed(),
+				y: angle.sin() * self.pattern.speed(),
+
+				.. Default::default()
+			},
+		})
+	}
+}
+
+/// Runs the bullet patterns.
+#[derive(Debug)]
+pub struct Runner {
+	settings: settings::Game,
+	active:   Vec<Emitter>,
+}
+
+impl Runner {
+	pub fn new(settings: &settings::Game) -> Self {
+		Runner {
+			settings: settings.clone(),
+			active:   Vec::new(),
+		}
+	}
+
+	/// Starts the named pattern, if the origin is missing the one in the pattern
+	/// is used.
+	pub fn spawn(&mut self, support: &Support, name: &str, origin: Option<Position>, color: Color) -> bool {
+		let pattern = match self.settings.pattern(name) {
+			Some(pattern) =>
+				pattern.clone(),
+
+			None => {
+				warn!("pattern: `{}` does not exist", name);
+				return false;
+			}
+		};
+
+		let origin = origin.unwrap_or_else(|| {
+			let (x, y) = pattern.origin().unwrap_or((0.5, 0.0));
+
+			Position {
+				x: x * support.aspect().width() as f32,
+				y: y * support.aspect().height() as f32,
+				z: 0.0,
+			}
+		});
+
+		// Follow the melody, if the pattern wants to.
+		let rotate = if pattern.steer() != 0.0 {
+			support.pitch().and_then(|p| p.semitones()).map(|s| s as f32 * pattern.steer()).unwrap_or(0.0)
+		}
+		else {
+			0.0
+		};
+
+		self.active.push(Emitter {
+			pattern: pattern,
+			origin:  origin,
+			color:   color,
+			rotate:  rotate,
+			depth:   0,
+
+			start: support.time(),
+			step:  0,
+		});
+
+		true
+	}
+
+	/// Runs the active patterns, returning the projectiles that have been fired.
+	pub fn update(&mut self, support: &Support, target: Position) -> Vec<Projectile> {
+		let mut result = Vec::new();
+		let mut index  = 0;
+
+		// Repeats push new emitters while going, so it's iterating by hand.
+		while index < self.active.len() {
+			loop {
+				let (steps, delay) = self.active[index].steps();
+				let step           = self.active[index].step;
+
+				if step >= steps || self.active[index].start + step as f64 * delay > support.time() {
+					break;
+				}
+
+				self.active[index].step += 1;
+
+				if let Some(child) = self.emit(index, step, target, &mut result) {
+					if self.active.len() < EMITTERS {
+						self.active.push(child);
+					}
+					else {
+						warn!("pattern: too many patterns running");
+					}
+				}
+			}
+
+			index += 1;
+		}
+
+		self.active.retain(|e| !e.is_done());
+
+		result
+	}
+
+	fn emit(&self, index: usize, step: u32, target: Position, result: &mut Vec<Projectile>) -> Option<Emitter> {
+		let emitter = &self.active[index];
+		let angle   = emitter.pattern.angle() + emitter.rotate;
+
+		match emitter.pattern.kind() {
+			&Kind::Fan { count, spread } => {
+				for i in 0 .. count {
+					let offset = if count > 1 {
+						spread * i as f32 / (count - 1) as f32 - spread / 2.0
+					}
+					else {
+						0.0
+					};
+
+					result.push(emitter.shot(angle + offset));
+				}
+			},
+
+			&Kind::Ring { count } => {
+				for i in 0 .. count {
+					result.push(emitter.shot(angle + 360.0 * i as f32 / count as f32));
+				}
+			},
+
+			&Kind::Spiral { count, turns, .. } => {
+				result.push(emitter.shot(angle + 360.0 * turns * step as f32 / count as f32));
+			},
+
+			&Kind::Aimed { .. } => {
+				let x = target.x - emitter.origin.x;
+				let y = target.y - emitter.origin.y;
+
+				result.push(emitter.shot(y.atan2(x).to_degrees()));
+			},
+
+			&Kind::Repeat { rotate, ref pattern, .. } => {
+				if emitter.depth >= DEPTH {
+					warn!("pattern: repeats nested too deep");
+					return None;
+				}
+
+				let pattern = match **pattern {
+					Child::Inline(ref pattern) =>
+						pattern.clone(),
+
+					Child::Named(ref name) =>
+						match self.settings.pattern(name) {
+							Some(pattern) =>
+								pattern.clone(),
+
+							None => {
+								warn!("pattern: `{}` does not exist", name);
+								return None;
+							}
+						},
+				};
+
+				return Some(Emitter {
+					pattern: pattern,
+					origin:  emitter.origin,
+					color:   emitter.color,
+					rotate:  emitter.rotate + rotate * step as f32,
+					depth:   emitter.depth + 1,
+
+					start: emitter.start + step as f64 * self.active[index].steps().1,
+					step:  0,
+				});
+			},
+		}
+
+		None
+	}
+}
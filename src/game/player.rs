@@ -1,14 +1,27 @@
 use std::ops::{Deref, DerefMut};
 
-use game::{Update, Support, Ship};
+use game::{Update, CanDamage, Support, Ship, Enemy, Position};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Status {
+	Alive,
+
+	Dead {
+		since: f64,
+	},
+
+	Invulnerable {
+		until: f64,
+	},
+}
 
 #[derive(Debug)]
 pub struct Player {
-	ship:  Ship,
-	name:  Option<String>,
-	score: u64,
+	ship: Ship,
+	name: Option<String>,
 
-	lives: u8,
+	lives:  u8,
+	status: Status,
 }
 
 impl Player {
@@ -21,16 +34,85 @@ impl Player {
 		self.velocity.pitch = 0.0;
 		self.velocity.yaw   = 0.0;
 	}
+
+	pub fn with_lives(mut self, lives: u8) -> Self {
+		self.lives = lives;
+		self
+	}
+
+	pub fn lives(&self) -> u8 {
+		self.lives
+	}
+
+	pub fn status(&self) -> Status {
+		self.status
+	}
+
+	/// Checks if the player is on the playfield.
+	pub fn is_alive(&self) -> bool {
+		match self.status {
+			Status::Dead { .. } =>
+				false,
+
+			_ =>
+				true,
+		}
+	}
+
+	/// Checks if the player can be hit.
+	pub fn is_vulnerable(&self) -> bool {
+		self.status == Status::Alive
+	}
+
+	/// Checks if the player has any lives left.
+	pub fn is_over(&self) -> bool {
+		!self.is_alive() && self.lives == 0
+	}
+
+	/// Checks if the player should be drawn, invulnerability blinks ten times a
+	/// second.
+	pub fn is_visible(&self, time: f64) -> bool {
+		match self.status {
+			Status::Alive =>
+				true,
+
+			Status::Dead { .. } =>
+				false,
+
+			Status::Invulnerable { until } =>
+				((until - time) * 10.0) as u64 % 2 == 0,
+		}
+	}
+
+	/// Kills the player losing a life.
+	pub fn kill(&mut self, time: f64) {
+		if !self.is_vulnerable() {
+			return;
+		}
+
+		self.lives  = self.lives.saturating_sub(1);
+		self.status = Status::Dead { since: time };
+	}
+
+	/// Brings the player back at the given position, invulnerable for the given
+	/// duration.
+	pub fn respawn(&mut self, time: f64, position: Position, duration: f64) {
+		self.reset();
+
+		self.position    = position;
+		self.orientation = Default::default();
+		self.status      = Status::Invulnerable { until: time + duration };
+	}
 }
 
 impl Default for Player {
 	fn default() -> Player {
 		Player {
-			ship:  Default::default(),
-			name:  None,
-			score: 0,
+			ship: Default::default(),
+			name: None,
 
-			lives: 3,
+			lives:  3,
+			status: Status::Alive,
 		}
 	}
 }
@@ -51,6 +133,24 @@ impl DerefMut for Player {
 
 impl Update for Player {
 	fn update(&mut self, support: &Support) {
+		match self.status {
+			Status::Dead { .. } =>
+				return,
+
+			Status::Invulnerable { until } if support.time() >= until =>
+				self.status = Status::Alive,
+
+			_ =>
+				(),
+		}
+
 		self.ship.update(support);
 	}
 }
+
+impl CanDamage<Player, Enemy> for Player {
+	fn can_damage(player: &Player, enemy: &Enemy) -> bool {
+		// Ramming into an enemy takes it down with the player.
+		player.is_alive()
+	}
+}
@@ -4,7 +4,14 @@ pub use self::plasma::Plasma;
 mod ray;
 pub use self::ray::Ray;
 
-use game::{Update, Alive, Support};
+use game::{Update, Alive, CanDamage, Support, Player, Enemy};
+
+/// Who fired the projectile.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Owner {
+	Player,
+	Enemy,
+}
 
 #[derive(Debug)]
 pub enum Projectile {
@@ -12,6 +19,18 @@ pub enum Projectile {
 	Ray(Ray),
 }
 
+impl Projectile {
+	pub fn owner(&self) -> Owner {
+		match self {
+			&Projectile::Plasma(ref p) =>
+				p.owner(),
+
+			&Projectile::Ray(ref r) =>
+				r.owner(),
+		}
+	}
+}
+
 impl Update for Projectile {
 	fn update(&mut self, support: &Support) {
 		match self {
@@ -35,3 +54,16 @@ impl Alive for Projectile {
 		}
 	}
 }
+
+impl CanDamage<Projectile, Player> for Projectile {
+	fn can_damage(projectile: &Projectile, player: &Player) -> bool {
+		projectile.owner() == Owner::Enemy && player.is_vulnerable()
+	}
+}
+
+impl CanDamage<Projectile, Enemy> for Projectile {
+	fn can_damage(projectile: &Projectile, enemy: &Enemy) -> bool {
+		// Enemies don't shoot each other.
+		projectile.owner() == Owner::Player
+	}
+}
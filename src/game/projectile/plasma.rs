@@ -1,9 +1,12 @@
 use util::{Aspect, Color};
 use game::{Update, Alive, Support, Position, Velocity};
+use game::projectile::Owner;
 
 #[derive(Debug)]
 pub enum Plasma {
 	Static {
+		owner: Owner,
+
 		radius: f32,
 		border: Color,
 
@@ -12,6 +15,8 @@ pub enum Plasma {
 	},
 
 	Dynamic {
+		owner: Owner,
+
 		min:  f32,
 		max:  f32,
 		step: f32,
@@ -100,3 +105,12 @@ impl Alive for Plasma {
 		}
 	}
 }
+
+impl Plasma {
+	pub fn owner(&self) -> Owner {
+		match self {
+			&Plasma::Static { owner, .. } | &Plasma::Dynamic { owner, .. } =>
+				owner,
+		}
+	}
+}
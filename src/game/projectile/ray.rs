@@ -1,9 +1,12 @@
 use util::{Aspect, Color};
 use game::{Update, Alive, Support, Position, Orientation, Velocity};
+use game::projectile::Owner;
 
 #[derive(Debug)]
 pub enum Ray {
 	Static {
+		owner: Owner,
+
 		start:    f64,
 		duration: f64,
 
@@ -16,6 +19,8 @@ pub enum Ray {
 	},
 
 	Dynamic {
+		owner: Owner,
+
 		start:    f64,
 		duration: f64,
 
@@ -105,3 +110,12 @@ impl Alive for Ray {
 		}
 	}
 }
+
+impl Ray {
+	pub fn owner(&self) -> Owner {
+		match self {
+			&Ray::Static { owner, .. } | &Ray::Dynamic { owner, .. } =>
+				owner,
+		}
+	}
+}
@@ -0,0 +1,133 @@
+use util::Ring;
+use settings;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Action {
+	Kill,
+	Dodge,
+}
+
+/// Keeps track of the score, actions are rewarded with a combo when they land
+/// close to a beat.
+///
+/// Beats are only known once they've passed, so actions are kept pending until
+/// either a beat shows up within the window or the window is over.
+#[derive(Debug)]
+pub struct Score {
+	settings: settings::Score,
+
+	points:     u64,
+	combo:      u32,
+	multiplier: u32,
+
+	beats:   Ring<f64>,
+	pending: Vec<(f64, Action)>,
+	dodged:  Option<f64>,
+}
+
+impl Score {
+	pub fn new(settings: &settings::Score) -> Self {
+		Score {
+			settings: settings.clone(),
+
+			points:     0,
+			combo:      0,
+			multiplier: 1,
+
+			beats:   Ring::new(16),
+			pending: Vec::new(),
+			dodged:  None,
+		}
+	}
+
+	pub fn points(&self) -> u64 {
+		self.points
+	}
+
+	pub fn combo(&self) -> u32 {
+		self.combo
+	}
+
+	pub fn multiplier(&self) -> u32 {
+		self.multiplier
+	}
+
+	/// Registers a beat at the given offset.
+	pub fn beat(&mut self, offset: f64) {
+		self.beats.push(offset);
+	}
+
+	/// Registers a kill at the given time.
+	pub fn kill(&mut self, time: f64) {
+		self.pending.push((time, Action::Kill));
+	}
+
+	/// Registers a dodge at the given time, dodges are only counted once per
+	/// window.
+	pub fn dodge(&mut self, time: f64) {
+		if self.pending.iter().any(|&(_, a)| a == Action::Dodge) {
+			return;
+		}
+
+		self.pending.push((time, Action::Dodge));
+	}
+
+	/// Registers a hit, the combo is lost.
+	pub fn hit(&mut self) {
+		self.combo = 0;
+		self.recompute();
+	}
+
+	/// Resolves the pending actions.
+	pub fn update(&mut self, time: f64) {
+		let window  = self.settings.window();
+		let pending = ::std::mem::replace(&mut self.pending, Vec::new());
+
+		for (at, action) in pending {
+			let beat = self.beats.iter().cloned()
+				.find(|&offset| (offset - at).abs() <= window);
+
+			match (beat, action) {
+				(Some(_), Action::Kill) => {
+					self.combo  += 1;
+					self.recompute();
+					self.points += self.settings.kill() * self.multiplier as u64;
+				},
+
+				// A dodge only counts once per beat.
+				(Some(offset), Action::Dodge) => {
+					if self.dodged != Some(offset) {
+						self.dodged  = Some(offset);
+						self.combo  += 1;
+						self.recompute();
+						self.points += self.settings.dodge() * self.multiplier as u64;
+					}
+				},
+
+				// No beat can show up anymore, so it's off-beat.
+				(None, Action::Kill) if time - at > window => {
+					self.points += self.settings.kill();
+					self.combo   = (self.combo as f64 * self.settings.decay()) as u32;
+					self.recompute();
+				},
+
+				(None, Action::Dodge) if time - at > window => {
+					self.points += self.settings.dodge();
+					self.combo   = (self.combo as f64 * self.settings.decay()) as u32;
+					self.recompute();
+				},
+
+				(None, action) =>
+					self.pending.push((at, action)),
+			}
+		}
+	}
+
+	fn recompute(&mut self) {
+		self.multiplier = 1 + self.combo / self.settings.combo();
+
+		if self.multiplier > self.settings.multiplier() {
+			self.multiplier = self.settings.multiplier();
+		}
+	}
+}
@@ -0,0 +1,110 @@
+use util::Aspect;
+use settings;
+use analyzer::{Beat, Side};
+use game::{Support, Ship, Position, Enemy};
+use game::enemy::Path;
+
+/// Turns analyzer beats into enemy waves.
+#[derive(Debug)]
+pub struct Spawner {
+	settings: settings::Game,
+	waves:    usize,
+}
+
+impl Spawner {
+	pub fn new(settings: &settings::Game) -> Self {
+		Spawner {
+			settings: settings.clone(),
+			waves:    0,
+		}
+	}
+
+	/// How many waves have been spawned so far.
+	pub fn waves(&self) -> usize {
+		self.waves
+	}
+
+	/// Spawns a wave for the given beat, `None` if neither the beat nor its band
+	/// have an enemy archetype.
+	///
+	/// Beats heard on one side spawn on that side of the playfield, left being
+	/// the top on horizontal aspects.
+	pub fn spawn(&mut self, support: &Support, beat: &Beat, player: Position) -> Option<Vec<Enemy>> {
+		let archetype = match beat.enemy().and_then(|name| self.settings.enemy(name)) {
+			Some(archetype) =>
+				archetype,
+
+			None =>
+				return None,
+		};
+
+		let width  = support.aspect().width() as f32;
+		let height = support.aspect().height() as f32;
+		let count  = archetype.count();
+		let color  = support.color(beat);
+
+		let difficulty = support.difficulty();
+
+		// Alternate sides and lanes between waves so consecutive waves don't
+		// overlap.
+		let flip = self.waves % 2 == 1;
+
+		// The part of the playfield the wave spawns in.
+		let (left, right) = match beat.side() {
+			Side::Left   => (0.0, width / 2.0),
+			Side::Center => (0.0, width),
+			Side::Right  => (width / 2.0, width),
+		};
+
+		let mut wave = Vec::with_capacity(count as usize);
+
+		for index in 0 .. count {
+			let mut ship = Ship::default();
+
+			ship.shape  = archetype.shape();
+			ship.face   = archetype.face();
+			ship.border = archetype.border();
+			ship.scale  = archetype.scale();
+
+			ship.velocity.yaw   = 1.0;
+			ship.velocity.pitch = 0.5;
+
+			ship.position = match archetype.path() {
+				Path::Sweep => Position {
+					x: match beat.side() {
+						Side::Left   => 0.0,
+						Side::Right  => width,
+						Side::Center => if flip { width } else { 0.0 },
+					},
+					y: height / 8.0 + index as f32 * 25.0 * archetype.scale(),
+					z: 0.0,
+				},
+
+				_ => {
+					let lane = (right - left) / (count + 1) as f32;
+					let skew = if flip { lane / 2.0 } else { 0.0 };
+
+					Position {
+						x: left + lane * (index + 1) as f32 - skew,
+						y: 0.0,
+						z: 0.0,
+					}
+				}
+			};
+
+			// Harder segments have faster enemies firing faster.
+			let mut enemy = Enemy::new(ship, archetype.path(), archetype.fire().faster(difficulty as f64),
+				archetype.speed() * difficulty, color, support.time());
+
+			if let Path::Dive = archetype.path() {
+				enemy.aim(player);
+			}
+
+			wave.push(enemy);
+		}
+
+		self.waves += 1;
+
+		Some(wave)
+	}
+}
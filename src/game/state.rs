@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use glium::glutin::Event;
+use glium::glutin::Event as Input;
 use glium::glutin::ElementState::{Pressed, Released};
 use glium::glutin::VirtualKeyCode as Key;
 
@@ -8,34 +8,55 @@ use ffmpeg::Rational;
 
 use util::Aspect;
 use settings;
-use analyzer::Analyzer;
-use game::{Update, Alive, Support, Position, Player, Ship, Projectile, Particle};
+use analyzer::{self, Analyzer, Beat};
+use game::{Update, Alive, CanDamage, Support, Event, Body, Position, Player, Enemy, Spawner, Runner, Score, Weapon, Projectile, Particle};
+use game::enemy::Shot;
+use game::player::Status;
 
 #[derive(Debug)]
 pub struct State {
 	player:      Player,
-	enemies:     Vec<Ship>,
+	weapon:      Weapon,
+	score:       Score,
+	spawner:     Spawner,
+	runner:      Runner,
+	enemies:     Vec<Enemy>,
 	projectiles: Vec<Projectile>,
 	particles:   Vec<Particle>,
+	events:      Vec<Event>,
 
 	settings: settings::Game,
 	aspect:   Rational,
 	keys:     HashSet<Key>,
 	tick:     usize,
+	time:     f64,
+	spawn:    Position,
+	paused:   bool,
+
+	// The beats whose wave has been spawned ahead of time and haven't landed
+	// yet.
+	telegraphed: Vec<Beat>,
+
+	// The current bar and beat in it.
+	bar: (u32, u32),
+
+	// The key of the music, if it's known.
+	key: Option<analyzer::Key>,
 }
 
 impl State {
 	pub fn new(settings: &settings::Game, aspect: Rational) -> Self {
-		let mut player = Player::default();
-
-		player.shape = settings.ship().shape();
+		let mut player = Player::default().with_lives(settings.player().lives());
 
-		player.position = Position {
+		let spawn = Position {
 			x: (aspect.width() as f32 / 2.0),
 			y: (aspect.height() as f32 - 20.0),
 			z: 0.0,
 		};
 
+		player.shape    = settings.ship().shape();
+		player.position = spawn;
+
 		if let Some(face) = settings.ship().face() {
 			player.face = face;
 		}
@@ -48,43 +69,66 @@ impl State {
 
 		State {
 			player:      player,
+			weapon:      Weapon::new(settings.weapon()),
+			score:       Score::new(settings.score()),
+			spawner:     Spawner::new(settings),
+			runner:      Runner::new(settings),
 			enemies:     Vec::new(),
 			projectiles: Vec::new(),
 			particles:   Vec::new(),
+			events:      Vec::new(),
 
 			settings: settings.clone(),
 			aspect:   aspect.reduce(),
 			keys:     HashSet::new(),
 			tick:     0,
+			time:     0.0,
+			spawn:    spawn,
+			paused:   false,
+
+			telegraphed: Vec::new(),
+
+			bar: (0, 0),
+
+			key: None,
 		}
 	}
 	
-	pub fn handle(&mut self, event: &Event) {
+	pub fn handle(&mut self, event: &Input) {
 		match event {
-			&Event::ReceivedCharacter(..) |
-			&Event::MouseMoved(..) |
-			&Event::MouseWheel(..) |
-			&Event::MouseInput(..) => (),
+			&Input::ReceivedCharacter(..) |
+			&Input::MouseMoved(..) |
+			&Input::MouseWheel(..) |
+			&Input::MouseInput(..) => (),
 
-			&Event::KeyboardInput(Pressed, _, Some(key)) => {
+			&Input::KeyboardInput(Pressed, _, Some(key)) => {
 				self.keys.insert(key);
 			},
 
-			&Event::KeyboardInput(Released, _, Some(key)) => {
+			&Input::KeyboardInput(Released, _, Some(key)) => {
 				self.keys.remove(&key);
 			},
 
-			&Event::KeyboardInput(..) => (),
+			&Input::KeyboardInput(..) => (),
 
 			_ => unreachable!()
 		}
 	}
 
-	pub fn player(&self) -> &Ship {
+	pub fn player(&self) -> &Player {
 		&self.player
 	}
 
-	pub fn enemies(&self) -> &[Ship] {
+	pub fn score(&self) -> &Score {
+		&self.score
+	}
+
+	/// The key of the music as of the last tick.
+	pub fn key(&self) -> Option<analyzer::Key> {
+		self.key
+	}
+
+	pub fn enemies(&self) -> &[Enemy] {
 		&self.enemies
 	}
 
@@ -96,27 +140,82 @@ impl State {
 		&self.particles
 	}
 
+	/// The events that happened during the last tick.
+	pub fn events(&self) -> &[Event] {
+		&self.events
+	}
+
+	/// The time of the last tick.
+	pub fn time(&self) -> f64 {
+		self.time
+	}
+
+	/// Pauses the game, held keys are forgotten since their release could be
+	/// missed.
+	pub fn pause(&mut self) {
+		self.paused = true;
+		self.keys.clear();
+	}
+
+	pub fn resume(&mut self) {
+		self.paused = false;
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.paused
+	}
+
+	/// The current bar and the beat in it, zero being the downbeat.
+	pub fn bar(&self) -> (u32, u32) {
+		self.bar
+	}
+
+	/// Checks if the game is over.
+	pub fn is_over(&self) -> bool {
+		self.player.is_over()
+	}
+
 	pub fn tick(&mut self, time: f64, analyzer: &mut Analyzer) {
-		// --
-		if let Ok(peaks) = analyzer.beats() {
-			for peak in peaks {
-				self.projectiles.push(Projectile::Plasma(::game::projectile::Plasma::Dynamic {
-					min:  1.0,
-					max:  5.0,
-					step: 0.2,
+		// Events only live for a tick.
+		self.events.clear();
+		self.time = time;
+
+		// Fetch the beats before the support borrows the analyzer.
+		let peaks    = analyzer.beats().unwrap_or(Vec::new());
+		let upcoming = if self.settings.telegraph() > 0.0 {
+			analyzer.upcoming(self.settings.telegraph())
+		}
+		else {
+			Vec::new()
+		};
 
-					radius: 1.0,
-					border: peak.band().color().unwrap_or(::util::Color::from("#fff")),
+		// The score works in analyzer time, since that's where beats live.
+		let beat = analyzer.at(time);
+
+		for peak in &peaks {
+			self.score.beat(peak.offset());
+		}
 
-					position: ::game::Position { x: 10.0, y: 10.0, z: 0.0 },
-					velocity: ::game::Velocity { x: 0.56, y: 1.0, .. Default::default() },
-				}));
+		// Follow the bars, telling everyone when a new one starts.
+		for event in analyzer.bars() {
+			if let analyzer::Event::Bar { index, beat, .. } = event {
+				if beat == 0 && self.bar != (index, beat) {
+					self.events.push(Event::Bar { index: index });
+				}
+
+				self.bar = (index, beat);
+			}
+		}
+
+		// Bring the player back if it's been dead long enough.
+		if let Status::Dead { since } = self.player.status() {
+			if self.player.lives() > 0 && time - since >= self.settings.player().respawn() {
+				self.player.respawn(time, self.spawn, self.settings.player().invulnerability());
 			}
 		}
-		// --
 
 		// Deal with the player.
-		{
+		if self.player.is_alive() {
 			// Reset the player state.
 			self.player.reset();
 
@@ -187,15 +286,81 @@ impl State {
 		{
 			// Create the support.
 			let support = Support::new(&self.settings, self.aspect, self.tick, time, analyzer);
+			self.key    = support.key();
 
 			// Update the player state.
 			self.player.update(&support);
 
-			// Update the enemies state.
+			// Fire while the key is held down.
+			if self.player.is_alive() && self.keys.contains(&Key::Space) {
+				if let Some(projectile) = self.weapon.fire(&support, self.player.position, !peaks.is_empty()) {
+					self.projectiles.push(projectile);
+				}
+			}
+
+			// Spawn the enemy waves and patterns for the beats, bands without either
+			// fall back to the `default` pattern, or the one of the segment.
+			for peak in &peaks {
+				let color = support.color(peak);
+
+				// The wave may have been spawned ahead of time already.
+				if let Some(index) = self.telegraphed.iter().position(|b| same(b, peak)) {
+					self.telegraphed.remove(index);
+				}
+				else if let Some(wave) = self.spawner.spawn(&support, peak, self.player.position) {
+					self.enemies.extend(wave);
+				}
+
+				if let Some(name) = peak.pattern() {
+					self.runner.spawn(&support, name, None, color);
+				}
+				else if peak.enemy().is_none() && self.settings.pattern(support.default_pattern()).is_some() {
+					self.runner.spawn(&support, support.default_pattern(), None, color);
+				}
+			}
+
+			// Telegraphed beats that didn't land by now got throttled away.
+			self.telegraphed.retain(|b| b.offset() > beat);
+
+			// Spawn the enemy waves for the beats about to land, they hold their
+			// fire until the beat.
+			for peak in &upcoming {
+				if self.telegraphed.iter().any(|b| same(b, peak)) {
+					continue;
+				}
+
+				self.telegraphed.push(peak.clone());
+
+				if let Some(wave) = self.spawner.spawn(&support, peak, self.player.position) {
+					let at = time + (peak.offset() - beat);
+
+					for mut enemy in wave {
+						enemy.arm(at);
+						self.enemies.push(enemy);
+					}
+				}
+			}
+
+			// Update the enemies state and let them fire.
 			for enemy in &mut self.enemies {
 				enemy.update(&support);
+
+				match enemy.fire(&support, self.player.position) {
+					Some(Shot::Projectile(projectile)) =>
+						self.projectiles.push(projectile),
+
+					Some(Shot::Pattern(name)) => {
+						self.runner.spawn(&support, &name, Some(enemy.position), enemy.color());
+					},
+
+					None =>
+						(),
+				}
 			}
 
+			// Run the patterns.
+			self.projectiles.extend(self.runner.update(&support, self.player.position));
+
 			// Update the projectiles state.
 			for projectile in &mut self.projectiles {
 				projectile.update(&support);
@@ -216,7 +381,125 @@ impl State {
 			self.particles.retain(|p| p.alive(&support));
 		}
 
+		// Check what hit what.
+		self.collide();
+
+		// Handle what happened.
+		for event in &self.events {
+			match event {
+				&Event::Hit { position } => {
+					self.player.kill(time);
+					self.score.hit();
+					self.particles.extend(Particle::explosion(position, 16));
+				},
+
+				&Event::Graze { .. } =>
+					self.score.dodge(beat),
+
+				&Event::Destroyed { position, .. } => {
+					self.score.kill(beat);
+					self.particles.extend(Particle::explosion(position, 8));
+				},
+
+				&Event::Bar { .. } =>
+					(),
+			}
+		}
+
+		// Resolve the pending score actions.
+		self.score.update(beat);
+
 		// Increase the current tick.
 		self.tick += 1;
 	}
+
+	fn collide(&mut self) {
+		let player = Body::from(&*self.player);
+		let graze  = Body::graze(&*self.player);
+		let bodies = self.enemies.iter().map(|e| Body::from(&**e)).collect::<Vec<_>>();
+
+		let mut hit       = false;
+		let mut grazed    = false;
+		let mut destroyed = vec![false; self.enemies.len()];
+		let mut spent     = vec![false; self.projectiles.len()];
+
+		// Check projectiles against the player and the enemies.
+		for (i, projectile) in self.projectiles.iter().enumerate() {
+			let body = Body::from(projectile);
+
+			// Rays go through everything, plasma is spent on the first hit.
+			let piercing = if let &Projectile::Ray(..) = projectile { true } else { false };
+
+			if Projectile::can_damage(projectile, &self.player) {
+				if body.collides(&player) {
+					hit      = true;
+					spent[i] = !piercing;
+
+					continue;
+				}
+
+				if body.collides(&graze) {
+					grazed = true;
+				}
+			}
+
+			for (j, enemy) in self.enemies.iter().enumerate() {
+				if destroyed[j] || !Projectile::can_damage(projectile, enemy) || !body.collides(&bodies[j]) {
+					continue;
+				}
+
+				destroyed[j] = true;
+				spent[i]     = !piercing;
+
+				if !piercing {
+					break;
+				}
+			}
+		}
+
+		// Check enemies ramming into the player.
+		for (j, enemy) in self.enemies.iter().enumerate() {
+			if destroyed[j] || !bodies[j].collides(&player) {
+				continue;
+			}
+
+			if Enemy::can_damage(enemy, &self.player) {
+				hit = true;
+			}
+
+			if Player::can_damage(&self.player, enemy) {
+				destroyed[j] = true;
+			}
+		}
+
+		if hit {
+			self.events.push(Event::Hit {
+				position: self.player.position,
+			});
+		}
+		else if grazed {
+			self.events.push(Event::Graze {
+				position: self.player.position,
+			});
+		}
+
+		for (j, enemy) in self.enemies.iter().enumerate().filter(|&(j, _)| destroyed[j]) {
+			self.events.push(Event::Destroyed {
+				position: enemy.position,
+				color:    enemy.color(),
+			});
+		}
+
+		// Remove the destroyed enemies and spent projectiles.
+		let mut index = 0;
+		self.enemies.retain(|_| { index += 1; !destroyed[index - 1] });
+
+		let mut index = 0;
+		self.projectiles.retain(|_| { index += 1; !spent[index - 1] });
+	}
+}
+
+/// Whether the beats are the same one, on the same band at the same time.
+fn same(a: &Beat, b: &Beat) -> bool {
+	a.offset() == b.offset() && a.band() == b.band()
 }
@@ -1,7 +1,8 @@
 use ffmpeg::Rational;
 
+use util::Color;
 use settings;
-use analyzer::Analyzer;
+use analyzer::{Analyzer, Beat, Envelope, Pitch, Key, Segment};
 
 pub struct Support<'s, 'a> {
 	settings: &'s settings::Game,
@@ -41,4 +42,81 @@ impl<'s, 'a> Support<'s, 'a> {
 	pub fn analyzer(&self) -> &Analyzer {
 		self.analyzer
 	}
+
+	/// The envelope of the music right now.
+	pub fn envelope(&self) -> Option<&Envelope> {
+		self.analyzer.envelope(self.analyzer.at(self.time))
+	}
+
+	/// The pitch of the music right now.
+	pub fn pitch(&self) -> Option<&Pitch> {
+		self.analyzer.pitch(self.analyzer.at(self.time))
+	}
+
+	/// The key of the music right now.
+	pub fn key(&self) -> Option<Key> {
+		self.analyzer.key(self.analyzer.at(self.time))
+	}
+
+	/// The segment of the song right now, if the structure is known.
+	pub fn segment(&self) -> Option<&Segment> {
+		self.analyzer.segment(self.analyzer.at(self.time))
+	}
+
+	/// How much harder the current segment is.
+	pub fn difficulty(&self) -> f32 {
+		self.hooks().map(|s| s.difficulty()).unwrap_or(1.0)
+	}
+
+	/// The pattern for beats of bands without an enemy or pattern.
+	pub fn default_pattern(&self) -> &str {
+		self.hooks().and_then(|s| s.pattern()).unwrap_or("default")
+	}
+
+	/// The color for the beat, from the pitch class or the key if the band
+	/// follows them, otherwise from the palette of the current segment or the
+	/// band.
+	pub fn color(&self, beat: &Beat) -> Color {
+		if beat.band().pitched() {
+			if let Some(pitch) = self.pitch() {
+				return Color::hsv(pitch.class() as f32 * 30.0, 0.8, 1.0);
+			}
+		}
+
+		if beat.band().keyed() {
+			if let Some(key) = self.key() {
+				return key.color();
+			}
+		}
+
+		let palette = self.hooks().map(|s| s.palette()).unwrap_or(&[]);
+		let index   = self.analyzer.settings().beat().bands().iter().position(|b| b == beat.band());
+
+		match index {
+			Some(index) if !palette.is_empty() =>
+				palette[index % palette.len()],
+
+			_ =>
+				beat.band().color().unwrap_or(Color::from("#fff")),
+		}
+	}
+
+	fn hooks(&self) -> Option<&settings::game::Segment> {
+		self.segment().and_then(|s| self.settings.segment(s.label()))
+	}
+
+	/// The game time of the next predicted beat.
+	pub fn next_beat(&self) -> Option<f64> {
+		let now = self.analyzer.at(self.time);
+
+		self.analyzer.next_beat(now).map(|b| self.time + (b - now))
+	}
+
+	/// Snaps the given game time to the beat grid split in the given
+	/// subdivisions, it's left as is while the tempo is unknown.
+	pub fn quantize(&self, time: f64, subdivision: u32) -> f64 {
+		let at = self.analyzer.at(time);
+
+		self.analyzer.quantize(at, subdivision).map(|b| time + (b - at)).unwrap_or(time)
+	}
 }
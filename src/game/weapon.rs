@@ -0,0 +1,68 @@
+use util::Color;
+use settings;
+use settings::game::Kind;
+use game::{Support, Position, Orientation, Velocity, Projectile};
+use game::projectile::{Owner, Plasma, Ray};
+
+/// The player's weapon.
+#[derive(Debug)]
+pub struct Weapon {
+	settings: settings::Weapon,
+	fired:    f64,
+}
+
+impl Weapon {
+	pub fn new(settings: &settings::Weapon) -> Self {
+		Weapon {
+			settings: settings.clone(),
+			fired:    0.0,
+		}
+	}
+
+	/// Fires from the given position if the weapon is ready, `beat` tells if
+	/// there's been a beat this tick.
+	pub fn fire(&mut self, support: &Support, position: Position, beat: bool) -> Option<Projectile> {
+		if self.settings.sync() {
+			if !beat {
+				return None;
+			}
+		}
+		else if support.time() - self.fired < self.settings.rate() {
+			return None;
+		}
+
+		self.fired = support.time();
+
+		let color = self.settings.color().unwrap_or(Color::from("#fff"));
+
+		Some(match self.settings.kind() {
+			Kind::Plasma =>
+				Projectile::Plasma(Plasma::Static {
+					owner: Owner::Player,
+
+					radius: self.settings.size(),
+					border: color,
+
+					position: position,
+					velocity: Velocity { y: -self.settings.speed(), .. Default::default() },
+				}),
+
+			Kind::Ray =>
+				Projectile::Ray(Ray::Static {
+					owner: Owner::Player,
+
+					start:    support.time(),
+					duration: self.settings.duration(),
+
+					width:  self.settings.size(),
+					border: color,
+
+					// Rays extend behind their position, turn them so they go up the
+					// playfield.
+					position:    position,
+					orientation: Orientation { yaw: 270.0, .. Default::default() },
+					velocity:    Default::default(),
+				}),
+		})
+	}
+}
@@ -2,20 +2,21 @@
 #![feature(associated_type_defaults, default_type_parameter_fallback)]
 
 use std::process::exit;
+use std::path::Path;
 use std::thread;
 use std::sync::{Arc, Mutex};
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
 
 extern crate ffmpeg;
-use ffmpeg::{time, Rational};
+use ffmpeg::Rational;
 
 #[macro_use]
 extern crate glium;
 use glium::{DisplayBuild, Surface};
 use glium::SwapBuffersError::{ContextLost, AlreadySwapped};
 use glium::glutin::{self, Event};
-use glium::glutin::ElementState::Released;
-use glium::glutin::VirtualKeyCode::Escape;
+use glium::glutin::ElementState::{Pressed, Released};
+use glium::glutin::VirtualKeyCode::{Escape, P};
 use glium::glutin::get_primary_monitor;
 
 extern crate openal;
@@ -48,10 +49,13 @@ extern crate strided;
 
 #[macro_use]
 mod util;
+use util::Clock;
+use util::clock::Wall;
 
 #[macro_use]
 mod settings;
 use settings::Settings;
+use settings::analyzer::Source;
 
 mod source;
 
@@ -67,8 +71,11 @@ use renderer::Renderer;
 mod analyzer;
 use analyzer::Analyzer;
 
+mod calibrate;
+
 static USAGE: &'static str = "
-Usage: nonagon [options] <input>
+Usage: nonagon calibrate [options] [<output>]
+       nonagon [options] <input>
        nonagon (-h | --help)
        nonagon (-v | --version)
 
@@ -77,24 +84,125 @@ Options:
 	-v --version    Show version.
 
 	-s --settings PATHS    The TOML settings files.
+	-S --start TIME        Start playing from the given time.
 	-a --audio-only        Do not show the video.
 	-m --no-music          Do not play the music.
 ";
 
+/// Messages for the music thread.
+enum Music {
+	/// Start playing, synchronized with the given time.
+	Start(f64),
+
+	/// Pause at the given time.
+	Pause(f64),
+
+	/// Resume at the given time.
+	Resume(f64),
+
+	/// Stop playing and exit.
+	Stop,
+}
+
+/// Handles the pending messages for the music thread, blocking while paused and
+/// moving the start forward by the time spent paused.
+///
+/// Returns `false` when the thread has to exit.
+fn control(receiver: &Receiver<Music>, start: &mut f64) -> bool {
+	loop {
+		match receiver.try_recv() {
+			Ok(Music::Pause(at)) => {
+				loop {
+					match receiver.recv() {
+						Ok(Music::Resume(to)) => {
+							*start += to - at;
+							break;
+						},
+
+						Ok(Music::Stop) | Err(..) =>
+							return false,
+
+						Ok(..) =>
+							(),
+					}
+				}
+			},
+
+			Ok(Music::Stop) | Err(TryRecvError::Disconnected) =>
+				return false,
+
+			Ok(..) =>
+				(),
+
+			Err(TryRecvError::Empty) =>
+				return true,
+		}
+	}
+}
+
 fn main() {
 	// Initialize libraries.
 	env_logger::init().unwrap();
 	ffmpeg::init().unwrap();
 
 	// Load the settings from the given args.
-	let settings = Settings::load(&Docopt::new(USAGE).
+	let args = Docopt::new(USAGE).
 		and_then(|d| d.parse()).
-		unwrap_or_else(|e| e.exit())).unwrap();
+		unwrap_or_else(|e| e.exit());
+
+	let settings = Settings::load(&args).unwrap();
 
 	debug!("{:#?}", settings);
 
-	// Spawn the source decoder.
-	let (a, v) = source::spawn(settings.input(), settings.audio().only());
+	// Measure the audio latency with a click track instead of playing, the
+	// result is written as settings to pass along with the others.
+	if args.get_bool("calibrate") {
+		let display = glutin::WindowBuilder::new()
+			.with_title(String::from("nonagon calibrate"))
+			.with_dimensions(320, 240)
+			.build_glium().unwrap_or_else(|err| {
+				println!("error: opengl: settings not supported: {}", err);
+				exit(4);
+			});
+
+		let mut sound = Sound::new(settings.audio()).unwrap_or_else(|err| {
+			println!("error: sound: {}", err);
+			exit(5);
+		});
+
+		let output = match args.get_str("<output>") {
+			"" => "latency.toml",
+			o  => o,
+		};
+
+		println!("tap space in time with the clicks, escape to cancel");
+
+		match calibrate::run(&display, &mut sound, &util::clock::Audio::new(Wall)) {
+			Some(latency) => {
+				if let Err(error) = calibrate::store(Path::new(output), latency) {
+					println!("error: calibrate: {}", error);
+					exit(6);
+				}
+
+				println!("latency: {:.3} seconds, written to {}", latency, output);
+			},
+
+			None =>
+				println!("calibration cancelled"),
+		}
+
+		return;
+	}
+
+	// The clock everything is synchronized on, it follows the music being
+	// played, or the wall clock when there's none.
+	let playback = Arc::new(util::clock::Audio::new(Wall));
+	let clock: Arc<Clock> = playback.clone();
+
+	// Spawn the source decoder, when starting from an offset go a little before
+	// it so the analyzer has some history.
+	let (a, v) = source::spawn(settings.input(), settings.audio().only(),
+		settings.start() - settings.analyzer().preroll());
 
 	// Check for errors for the audio decoder.
 	let mut audio = match a {
@@ -120,7 +228,7 @@ fn main() {
 		},
 
 		Ok(v) =>
-			v
+			v.map(|v| v.with_clock(clock.clone()))
 	};
 
 	// Calculate the window size based on the monitor dimension.
@@ -166,12 +274,12 @@ fn main() {
 		.with_dimensions(width, height)
 		.with_depth_buffer(24);
 
-	// Enable vsync if the settings says so.
+	// Enable vsync if the settings say so.
 	if settings.video().vsync() {
 		display = display.with_vsync();
 	}
 
-	// Enable multisampling if the settings says so.
+	// Enable multisampling if the settings say so.
 	if let Some(value) = settings.video().multisampling() {
 		display = display.with_multisampling(value);
 	}
@@ -191,11 +299,72 @@ fn main() {
 		exit(5);
 	})));
 
+	// Pick the sensitivity of the bands asking for a density of beats, unless
+	// the beats only come from a beatmap, the calibrated thresholds are cached
+	// and written out if requested.
+	let calibrated = match analyzer::calibrate::calibrate(settings.input(), settings.analyzer()) {
+		Ok(calibrated) => {
+			if let Some(path) = settings.analyzer().calibrate().output() {
+				if let Err(error) = analyzer::calibrate::store(path, &calibrated) {
+					warn!("calibrate: could not write {}: {}", path.display(), error);
+				}
+			}
+
+			calibrated
+		},
+
+		Err(error) => {
+			warn!("calibrate: {}, keeping the configured thresholds", error);
+			settings.analyzer().clone()
+		}
+	};
+
 	// Create the analyzer.
 	//
 	// It's in an Arc<Mutex<_>> because it's accessed both from the main thread
 	// and the music thread.
-	let analyzer = Arc::new(Mutex::new(Analyzer::spawn(settings.analyzer())));
+	let analyzer = Arc::new(Mutex::new(Analyzer::spawn(&calibrated)
+		.with_clock(clock.clone())
+		.with_latency(settings.audio().latency())));
+
+	// Analyze the whole file ahead of time if requested, this gives the game
+	// lookahead on the beats.
+	if settings.analyzer().offline() {
+		match analyzer::offline::analysis(settings.input(), &calibrated) {
+			Ok(analysis) =>
+				analyzer.lock().unwrap().preload(analysis, settings.start()),
+
+			Err(error) =>
+				warn!("offline: {}, falling back to realtime analysis", error),
+		}
+	}
+
+	// Load the beatmap if the beats come from it, they either replace the
+	// detected ones or are merged with them.
+	//
+	// A MIDI file also brings its tempo map.
+	if settings.analyzer().source() != Source::Detect {
+		let path    = analyzer::beatmap::path(settings.input(), settings.analyzer());
+		let replace = settings.analyzer().source() == Source::Beatmap;
+
+		let result = if analyzer::beatmap::is_midi(&path) {
+			analyzer::midi::load(&path, &calibrated).map(|sequence| {
+				analyzer.lock().unwrap().fix(sequence.tempo);
+				sequence.beats
+			})
+		}
+		else {
+			analyzer::beatmap::load(&path, &calibrated, settings.game())
+		};
+
+		match result {
+			Ok(beats) =>
+				analyzer.lock().unwrap().chart(beats, settings.start(), replace),
+
+			Err(error) =>
+				warn!("beatmap: {}: {}, falling back to detected beats", path.display(), error),
+		}
+	}
 
 	// Create the state to keep track of the game.
 	let mut state = State::new(settings.game(), aspect);
@@ -205,11 +374,14 @@ fn main() {
 	let music = {
 		let analyzer = analyzer.clone();
 		let sound    = sound.clone();
+		let clock    = clock.clone();
+		let playback = playback.clone();
 		let music    = settings.audio().music();
 		let cache    = settings.analyzer().min_cache();
+		let from     = settings.start();
 
-		// Channel for killing.
-		let (sender, receiver) = channel::<f64>();
+		// Channel for controlling.
+		let (sender, receiver) = channel::<Music>();
 
 		(sender, thread::spawn(move || {
 			// Keeps track of how far in stream we got.
@@ -219,14 +391,23 @@ fn main() {
 			let mut duration = 0.0;
 
 			// Wait for the game to be ready.
-			let start = receiver.recv().unwrap();
+			let mut start = match receiver.recv() {
+				Ok(Music::Start(start)) =>
+					start,
+
+				_ =>
+					return,
+			};
 
 			// Synchronize the source start.
 			audio.start(start);
 
+			// Whether the analyzer has been told about the preroll.
+			let mut primed = false;
+
 			loop {
 				// Return if the main has exited.
-				if receiver.try_recv().is_ok() {
+				if !control(&receiver, &mut start) {
 					return;
 				}
 
@@ -241,6 +422,18 @@ fn main() {
 				// Take out the frame value.
 				let frame = frame.unwrap();
 
+				// Frames before the start point are only there to prime the
+				// analyzer.
+				if audio.time(&frame) < from {
+					if !primed {
+						analyzer.lock().unwrap().prime(from - audio.time(&frame));
+						primed = true;
+					}
+
+					analyzer.lock().unwrap().feed(frame);
+					continue;
+				}
+
 				// Only play the music if it's not muted.
 				if music {
 					sound.lock().unwrap().play(&frame);
@@ -250,7 +443,7 @@ fn main() {
 				duration += (1.0 / 44100.0) * frame.samples() as f64;
 
 				// Return if main has exited.
-				if receiver.try_recv().is_ok() {
+				if !control(&receiver, &mut start) {
 					return;
 				}
 
@@ -264,29 +457,35 @@ fn main() {
 					// correct.
 					offset += duration;
 
-					// We need the current time so we don't oversleep.
-					let current = time::relative() as f64 / 1_000_000.0;
+					// Sleep in small portions so we can check for liveness, the
+					// remaining time is recomputed every time since a pause moves the
+					// start.
+					loop {
+						// Follow what's actually being played, the music started at
+						// `start`.
+						if let Some(position) = sound.lock().unwrap().position() {
+							playback.update(start + position);
+						}
+
+						// We need the current time so we don't oversleep.
+						let current = clock.now();
 
-					// Correct the duration considering time that has passed since we
-					// fetched the samples.
-					let mut corrected = offset - (current - start);
+						// Correct the duration considering time that has passed since we
+						// fetched the samples.
+						let corrected = offset - (current - start);
 
-					// Sleep in small portions so we can check for liveness.
-					while corrected >= 0.1 {
-						corrected -= 0.1;
-						time::sleep((0.1 * 1_000_000.0) as u32).unwrap();
+						if corrected <= 0.0 {
+							break;
+						}
+
+						clock.sleep(corrected.min(0.1));
 
 						// Return if the main has exited.
-						if receiver.try_recv().is_ok() {
+						if !control(&receiver, &mut start) {
 							return;
 						}
 					}
 
-					// Do a final sleep in case the loop is done with a leftover.
-					if corrected > 0.0 {
-						time::sleep((corrected * 1_000_000.0) as u32).unwrap();
-					}
-
 					// Reset the duration for the next cycle.
 					duration = 0.0;
 				}
@@ -302,15 +501,17 @@ fn main() {
 
 	// Synchronize start times.
 	{
-		let start = time::relative() as f64 / 1_000_000.0;
+		let start = clock.now();
 
+		// The video synchronizes on frame timestamps, so it has to start back in
+		// time by the offset.
 		if let Some(video) = video.as_mut() {
-			video.start(start);
+			video.start(start - settings.start());
 		}
 
 		analyzer.lock().unwrap().start(start);
 
-		music.0.send(start).unwrap();
+		music.0.send(Music::Start(start)).unwrap();
 	}
 
 	// Show the window.
@@ -320,18 +521,27 @@ fn main() {
 	}
 
 	// The previous time.
-	let mut previous = time::relative() as f64 / 1_000_000.0;
+	let mut previous = clock.now();
 
 	// The accumulated lag.
 	let mut lag = 0.0;
 
+	// When the game has been paused, if it is.
+	let mut paused: Option<f64> = None;
+
+	// The total time spent paused, the game time doesn't include it.
+	let mut idle = 0.0;
+
 	'game: loop {
-		let current = time::relative() as f64 / 1_000_000.0;
+		let current = clock.now();
 		let elapsed = current - previous;
 
 		previous  = current;
 		lag      += elapsed;
 
+		// Whether the pause has to be toggled.
+		let mut toggle = false;
+
 		// Fetch the events and handle them.
 		for event in display.poll_events() {
 			match event {
@@ -341,12 +551,25 @@ fn main() {
 				Event::Refresh  => (),
 				Event::Moved(x, y)    => (),
 				Event::Focused(true)  => (),
-				Event::Focused(false) => (),
+
+				// Pause when losing focus, if the settings say so.
+				Event::Focused(false) =>
+					if settings.game().autopause() && paused.is_none() {
+						toggle = true;
+					},
 
 				// When the window is closed or ESC is pressed, quit the game.
 				Event::Closed | Event::KeyboardInput(Released, _, Some(Escape)) =>
 					break 'game,
 
+				// P toggles the pause, the press is kept from the state too so the
+				// key isn't held down there forever.
+				Event::KeyboardInput(Pressed, _, Some(P)) =>
+					(),
+
+				Event::KeyboardInput(Released, _, Some(P)) =>
+					toggle = !toggle,
+
 				// The window has been resized.
 				Event::Resized(w, h) => {
 					// Cache the new dimension.
@@ -363,10 +586,47 @@ fn main() {
 			}
 		}
 
+		// Pause or resume everything, the music thread, video and analyzer move
+		// their start forward by the time spent paused.
+		if toggle {
+			if let Some(at) = paused.take() {
+				idle += current - at;
+
+				if let Some(video) = video.as_mut() {
+					video.resume(current);
+				}
+
+				analyzer.lock().unwrap().resume(current);
+				sound.lock().unwrap().resume();
+				let _ = music.0.send(Music::Resume(current));
+
+				state.resume();
+			}
+			else {
+				paused = Some(current);
+
+				if let Some(video) = video.as_mut() {
+					video.pause(current);
+				}
+
+				analyzer.lock().unwrap().pause(current);
+				sound.lock().unwrap().pause();
+				let _ = music.0.send(Music::Pause(current));
+
+				state.pause();
+			}
+		}
+
+		// Once the game is over the state is frozen, the music keeps going until
+		// the player quits, while paused everything is frozen.
+		if state.is_over() || paused.is_some() {
+			lag = 0.0;
+		}
+
 		// Make sure the state gets updated in splits of `step` seconds.
 		while lag >= settings.game().step() {
-			// Run an update tick.
-			state.tick(current - lag, &mut analyzer.lock().unwrap());
+			// Run an update tick, the time spent paused is not part of the game.
+			state.tick(current - idle - lag, &mut analyzer.lock().unwrap());
 
 			lag -= settings.game().step();
 		}
@@ -409,6 +669,6 @@ fn main() {
 	}
 
 	// Ensure the music thread is closed.
-	let _ = music.0.send(0.0);
+	let _ = music.0.send(Music::Stop);
 	music.1.join().unwrap();
 }
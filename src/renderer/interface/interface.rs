@@ -1,5 +1,6 @@
 use glium::{Display, Surface};
 
+use log::LogLevel;
 use lzma;
 
 use renderer::{Render, Support};
@@ -38,15 +39,46 @@ impl<'a> Interface<'a> {
 
 impl<'a> Render<game::State> for Interface<'a> {
 	fn render<S: Surface + 'static>(&self, target: &mut S, support: &Support, state: &Self::State) {
-		let mut face = self.face(target, support, &self.normal)
-			.color("#f00")
-			.size(1);
-
-		face.draw(&format!("FPS={:.0} min={:.0}ms max={:.0}ms avg={:.0}ms",
-			1.0 / support.debug().avg_frame_time(),
-			support.debug().min_frame_time() * 1_000.0,
-			support.debug().max_frame_time() * 1_000.0,
-			support.debug().avg_frame_time() * 1_000.0),
-		5, 30);
+		{
+			let mut face = self.face(target, support, &self.normal)
+				.color("#f00")
+				.size(1);
+
+			face.draw(&format!("SCORE={} x{} COMBO={}",
+				state.score().points(),
+				state.score().multiplier(),
+				state.score().combo()),
+			5, 30);
+
+			face.draw(&format!("LIVES={}", state.player().lives()), 5, 50);
+
+			if log_enabled!(LogLevel::Debug) {
+				face.draw(&format!("FPS={:.0} min={:.0}ms max={:.0}ms avg={:.0}ms",
+					1.0 / support.debug().avg_frame_time(),
+					support.debug().min_frame_time() * 1_000.0,
+					support.debug().max_frame_time() * 1_000.0,
+					support.debug().avg_frame_time() * 1_000.0),
+				5, 70);
+
+				if let Some(key) = state.key() {
+					face.draw(&format!("KEY={}", key.name()), 5, 90);
+				}
+			}
+		}
+
+		if state.is_over() {
+			let mut face = self.face(target, support, &self.bold)
+				.color("#f00")
+				.size(3);
+
+			face.draw("GAME OVER", 5, 100);
+		}
+		else if state.is_paused() {
+			let mut face = self.face(target, support, &self.bold)
+				.color("#fff")
+				.size(3);
+
+			face.draw("PAUSED", 5, 100);
+		}
 	}
 }
@@ -39,10 +39,13 @@ impl<'a> Renderer<'a> {
 		self.background.render(target, &self.support, state, frame);
 		self.support.update(time, self.background.texture());
 
-		self.ship.render(target, &self.support, state.player());
+		// the player blinks while invulnerable and disappears while dead
+		if state.player().is_visible(state.time()) {
+			self.ship.render(target, &self.support, &**state.player());
+		}
 
 		for enemy in state.enemies() {
-			self.ship.render(target, &self.support, enemy);
+			self.ship.render(target, &self.support, &**enemy);
 		}
 
 		for projectile in state.projectiles() {
@@ -1,4 +1,6 @@
 use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::env;
 
 use docopt::ArgvMap;
 
@@ -7,10 +9,39 @@ use toml::{Value, ParserError};
 use settings::Load;
 use util::Color;
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Debug)]
 pub struct Analyzer {
+	offline:  bool,
+	cache:    PathBuf,
+	channels: Channels,
+	source:   Source,
+	beatmap:  Option<PathBuf>,
+
 	window:   Window,
 	beat:     Beat,
+	tempo:    Tempo,
+	bar:      Bar,
+
+	calibrate: Calibrate,
+}
+
+impl Default for Analyzer {
+	fn default() -> Self {
+		Analyzer {
+			offline:  false,
+			cache:    env::temp_dir().join("nonagon"),
+			channels: Channels::Mono,
+			source:   Source::Detect,
+			beatmap:  None,
+
+			window: Default::default(),
+			beat:   Default::default(),
+			tempo:  Default::default(),
+			bar:    Default::default(),
+
+			calibrate: Default::default(),
+		}
+	}
 }
 
 impl Load for Analyzer {
@@ -20,6 +51,47 @@ impl Load for Analyzer {
 		if let Some(toml) = top.get("analyzer") {
 			let toml = expect!(toml.as_table(), "`analyzer` must be a table");
 
+			if let Some(value) = toml.get("offline") {
+				self.offline = expect!(value.as_bool(), "`analyzer.offline` must be a boolean");
+			}
+
+			if let Some(value) = toml.get("cache") {
+				self.cache = PathBuf::from(expect!(value.as_str(), "`analyzer.cache` must be a string"));
+			}
+
+			if let Some(value) = toml.get("channels") {
+				self.channels = match expect!(value.as_str(), "`analyzer.channels` must be a string") {
+					"mono" =>
+						Channels::Mono,
+
+					"stereo" =>
+						Channels::Stereo,
+
+					_ =>
+						expect!("`analyzer.channels` must be either \"mono\" or \"stereo\""),
+				};
+			}
+
+			if let Some(value) = toml.get("source") {
+				self.source = match expect!(value.as_str(), "`analyzer.source` must be a string") {
+					"detect" =>
+						Source::Detect,
+
+					"beatmap" =>
+						Source::Beatmap,
+
+					"merge" =>
+						Source::Merge,
+
+					_ =>
+						expect!("`analyzer.source` must be one of \"detect\", \"beatmap\" or \"merge\""),
+				};
+			}
+
+			if let Some(value) = toml.get("beatmap") {
+				self.beatmap = Some(PathBuf::from(expect!(value.as_str(), "`analyzer.beatmap` must be a string")));
+			}
+
 			if let Some(toml) = toml.get("window") {
 				try!(self.window.load(args, toml));
 			}
@@ -27,6 +99,18 @@ impl Load for Analyzer {
 			if let Some(toml) = toml.get("beat") {
 				try!(self.beat.load(args, toml));
 			}
+
+			if let Some(toml) = toml.get("tempo") {
+				try!(self.tempo.load(args, toml));
+			}
+
+			if let Some(toml) = toml.get("bar") {
+				try!(self.bar.load(args, toml));
+			}
+
+			if let Some(toml) = toml.get("calibrate") {
+				try!(self.calibrate.load(args, toml));
+			}
 		}
 
 		Ok(())
@@ -34,6 +118,36 @@ impl Load for Analyzer {
 }
 
 impl Analyzer {
+	/// Whether the whole file is analyzed before playing.
+	#[inline(always)]
+	pub fn offline(&self) -> bool {
+		self.offline
+	}
+
+	/// The directory where the offline analysis is cached.
+	#[inline(always)]
+	pub fn cache(&self) -> &Path {
+		&self.cache
+	}
+
+	/// Whether the channels are analyzed mixed down or on their own.
+	#[inline(always)]
+	pub fn channels(&self) -> Channels {
+		self.channels
+	}
+
+	/// Where the beats come from.
+	#[inline(always)]
+	pub fn source(&self) -> Source {
+		self.source
+	}
+
+	/// The beatmap to use instead of the one next to the input.
+	#[inline(always)]
+	pub fn beatmap(&self) -> Option<&Path> {
+		self.beatmap.as_ref().map(|p| p.as_ref())
+	}
+
 	#[inline(always)]
 	pub fn window(&self) -> &Window {
 		&self.window
@@ -44,6 +158,41 @@ impl Analyzer {
 		&self.beat
 	}
 
+	#[inline(always)]
+	pub fn tempo(&self) -> &Tempo {
+		&self.tempo
+	}
+
+	#[inline(always)]
+	pub fn bar(&self) -> &Bar {
+		&self.bar
+	}
+
+	#[inline(always)]
+	pub fn calibrate(&self) -> &Calibrate {
+		&self.calibrate
+	}
+
+	/// Changes the threshold sensitivity of the band at the given index.
+	pub fn with_sensitivity(mut self, index: usize, sensitivity: f64) -> Self {
+		self.beat.bands[index].threshold.sensitivity = sensitivity;
+		self
+	}
+
+	/// Seconds of audio the analyzer needs before a point to have a full
+	/// threshold history.
+	pub fn preroll(&self) -> f64 {
+		let mut size = self.beat().threshold().size();
+
+		for band in self.beat().bands() {
+			if band.threshold().size() > size {
+				size = band.threshold().size();
+			}
+		}
+
+		(self.window().size() + size * self.window().hop()) as f64 / 44100.0
+	}
+
 	pub fn min_cache(&self) -> f64 {
 		let mut result = (1.0 / 44100.0) * (self.beat().threshold().size() * 2 + 1) as f64;
 
@@ -59,6 +208,25 @@ impl Analyzer {
 	}
 }
 
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Channels {
+	Mono,
+	Stereo,
+}
+
+/// Where the beats come from.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Source {
+	/// Only the detected beats.
+	Detect,
+
+	/// Only the beats in the beatmap.
+	Beatmap,
+
+	/// Both the detected beats and the ones in the beatmap.
+	Merge,
+}
+
 #[derive(Clone, Debug)]
 pub struct Window {
 	size:   usize,
@@ -81,23 +249,36 @@ impl Load for Window {
 		let top = expect!(toml.as_table(), "`analyzer.window` must be a table");
 
 		if let Some(value) = top.get("size") {
-			self.size = expect!(value.as_integer(), "`analyzer.window.size` must be an integer") as usize;
+			let value = expect!(value.as_integer(), "`analyzer.window.size` must be an integer");
+
+			if value < 1 {
+				expect!("`analyzer.window.size` must be greater than zero");
+			}
+
+			self.size = value as usize;
 		}
 
 		if let Some(value) = top.get("hop") {
-			self.hop = expect!(value.as_integer(), "`analyzer.window.hop` must be an integer") as usize;
+			let value = expect!(value.as_integer(), "`analyzer.window.hop` must be an integer");
+
+			if value < 1 {
+				expect!("`analyzer.window.hop` must be greater than zero");
+			}
+
+			self.hop = value as usize;
 		}
 
 		if let Some(value) = top.get("filter") {
 			match value {
 				&Value::String(ref filter) =>
-					self.filter = Filter::from(filter),
+					self.filter = expect!(Filter::parse(filter),
+						"`analyzer.window.filter` must be one of \"hann\", \"hamming\", \"blackman\", \"blackman-harris\" or \"flat-top\""),
 
 				&Value::Boolean(false) =>
 					self.filter = Filter::None,
 
 				_ =>
-					expect!("`analyzer.window.filter` must be either \"hamming\" or false"),
+					expect!("`analyzer.window.filter` must be either a string or false"),
 			}
 		}
 
@@ -129,17 +310,33 @@ impl Window {
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum Filter {
 	None,
+	Hann,
 	Hamming,
+	Blackman,
+	BlackmanHarris,
+	FlatTop,
 }
 
-impl<T: AsRef<str>> From<T> for Filter {
-	fn from(value: T) -> Filter {
+impl Filter {
+	pub fn parse<T: AsRef<str>>(value: T) -> Option<Filter> {
 		match value.as_ref() {
+			"hann" =>
+				Some(Filter::Hann),
+
 			"hamming" =>
-				Filter::Hamming,
+				Some(Filter::Hamming),
+
+			"blackman" =>
+				Some(Filter::Blackman),
+
+			"blackman-harris" =>
+				Some(Filter::BlackmanHarris),
+
+			"flat-top" =>
+				Some(Filter::FlatTop),
 
 			_ =>
-				panic!("unsupported filter"),
+				None,
 		}
 	}
 }
@@ -209,22 +406,68 @@ impl Beat {
 	}
 }
 
-#[derive(Clone, Debug)]
+#[derive(PartialEq, Clone, Debug)]
 pub struct Band {
-	name:  Option<String>,
-	color: Option<Color>,
+	name:    Option<String>,
+	color:   Option<Color>,
+	pitched: bool,
+	keyed:   bool,
+	enemy:   Option<String>,
+	pattern: Option<String>,
+	track:   Option<u32>,
+	channel: Option<u8>,
 
 	range:     Range<u32>,
+	method:    Method,
 	threshold: Threshold,
 	throttle:  f64,
 }
 
+/// The onset detection function of a band.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Method {
+	/// What male does.
+	Default,
+
+	/// Spectral flux, suits drums.
+	Flux,
+
+	/// High frequency content, suits hi-hats.
+	Hfc,
+
+	/// Phase deviation, suits soft tonal attacks.
+	Phase,
+
+	/// Complex domain, suits tonal attacks.
+	Complex,
+}
+
+impl Method {
+	/// The name used in the settings.
+	pub fn name(&self) -> &'static str {
+		match *self {
+			Method::Default => "default",
+			Method::Flux    => "flux",
+			Method::Hfc     => "hfc",
+			Method::Phase   => "phase",
+			Method::Complex => "complex",
+		}
+	}
+}
+
 impl Default for Band {
 	fn default() -> Self {
 		Band {
 			name:      None,
 			color:     None,
+			pitched:   false,
+			keyed:     false,
+			enemy:     None,
+			pattern:   None,
+			track:     None,
+			channel:   None,
 			range:     Range { start: 0, end: 0 },
+			method:    Method::Default,
 			threshold: Default::default(),
 			throttle:  0.0,
 		}
@@ -241,8 +484,55 @@ impl Load for Band {
 		}
 
 		if let Some(value) = top.get("color") {
-			self.color = Some(Color::from(expect!(value.as_str(),
-				"`analyzer.beat.band.*.color` must be a string")));
+			match expect!(value.as_str(), "`analyzer.beat.band.*.color` must be a string") {
+				"pitch" => {
+					self.color   = None;
+					self.pitched = true;
+					self.keyed   = false;
+				},
+
+				"key" => {
+					self.color   = None;
+					self.pitched = false;
+					self.keyed   = true;
+				},
+
+				color => {
+					self.color   = Some(Color::from(color));
+					self.pitched = false;
+					self.keyed   = false;
+				}
+			}
+		}
+
+		if let Some(value) = top.get("enemy") {
+			self.enemy = Some(expect!(value.as_str(),
+				"`analyzer.beat.band.*.enemy` must be a string").to_owned());
+		}
+
+		if let Some(value) = top.get("pattern") {
+			self.pattern = Some(expect!(value.as_str(),
+				"`analyzer.beat.band.*.pattern` must be a string").to_owned());
+		}
+
+		if let Some(value) = top.get("track") {
+			match expect!(value.as_integer(), "`analyzer.beat.band.*.track` must be an integer") {
+				track if track >= 1 =>
+					self.track = Some(track as u32),
+
+				_ =>
+					expect!("`analyzer.beat.band.*.track` must be greater than zero"),
+			}
+		}
+
+		if let Some(value) = top.get("channel") {
+			match expect!(value.as_integer(), "`analyzer.beat.band.*.channel` must be an integer") {
+				channel if channel >= 1 && channel <= 16 =>
+					self.channel = Some(channel as u8),
+
+				_ =>
+					expect!("`analyzer.beat.band.*.channel` must be between 1 and 16"),
+			}
 		}
 
 		if let Some(value) = top.get("range") {
@@ -258,6 +548,10 @@ impl Load for Band {
 					let hi = expect!(range[1].as_integer(),
 						"`analyzer.beat.band.*.range.1` must be an integer");
 
+					if lo < 0 || lo > hi {
+						expect!("`analyzer.beat.band.*.range` must not be negative or reversed");
+					}
+
 					self.range = lo as u32 .. hi as u32;
 				},
 
@@ -269,6 +563,28 @@ impl Load for Band {
 			}
 		}
 
+		if let Some(value) = top.get("method") {
+			self.method = match expect!(value.as_str(), "`analyzer.beat.band.*.method` must be a string") {
+				"default" =>
+					Method::Default,
+
+				"flux" =>
+					Method::Flux,
+
+				"hfc" =>
+					Method::Hfc,
+
+				"phase" =>
+					Method::Phase,
+
+				"complex" =>
+					Method::Complex,
+
+				_ =>
+					expect!("`analyzer.beat.band.*.method` must be one of \"default\", \"flux\", \"hfc\", \"phase\" or \"complex\""),
+			};
+		}
+
 		if let Some(value) = top.get("throttle") {
 			self.throttle = expect!(value.as_float(), "`analyzer.beat.band.*.throttle` must be a float");
 		}
@@ -290,11 +606,61 @@ impl Band {
 		self.color
 	}
 
+	/// Whether the color comes from the pitch class of the music.
+	#[inline(always)]
+	pub fn pitched(&self) -> bool {
+		self.pitched
+	}
+
+	/// Whether the color comes from the key of the music.
+	#[inline(always)]
+	pub fn keyed(&self) -> bool {
+		self.keyed
+	}
+
+	#[inline(always)]
+	pub fn enemy(&self) -> Option<&str> {
+		self.enemy.as_ref().map(|n| n.as_ref())
+	}
+
+	#[inline(always)]
+	pub fn pattern(&self) -> Option<&str> {
+		self.pattern.as_ref().map(|n| n.as_ref())
+	}
+
+	/// The MIDI track mapped to the band, counting from 1.
+	#[inline(always)]
+	pub fn track(&self) -> Option<u32> {
+		self.track
+	}
+
+	/// The MIDI channel mapped to the band, counting from 1.
+	#[inline(always)]
+	pub fn channel(&self) -> Option<u8> {
+		self.channel
+	}
+
+	/// Whether notes in the given MIDI track and channel, both counting from
+	/// one, are beats for the band.
+	pub fn midi(&self, track: u32, channel: u8) -> bool {
+		if self.track.is_none() && self.channel.is_none() {
+			return false;
+		}
+
+		self.track.map(|t| t == track).unwrap_or(true) &&
+		self.channel.map(|c| c == channel).unwrap_or(true)
+	}
+
 	#[inline(always)]
 	pub fn range(&self) -> &Range<u32> {
 		&self.range
 	}
 
+	#[inline(always)]
+	pub fn method(&self) -> Method {
+		self.method
+	}
+
 	#[inline(always)]
 	pub fn threshold(&self) -> &Threshold {
 		&self.threshold
@@ -306,10 +672,11 @@ impl Band {
 	}
 }
 
-#[derive(Clone, Debug)]
+#[derive(PartialEq, Clone, Debug)]
 pub struct Threshold {
 	size:        usize,
 	sensitivity: f64,
+	density:     Option<f64>,
 }
 
 impl Default for Threshold {
@@ -317,6 +684,7 @@ impl Default for Threshold {
 		Threshold {
 			size:        25,
 			sensitivity: 1.5,
+			density:     None,
 		}
 	}
 }
@@ -326,13 +694,32 @@ impl Load for Threshold {
 		let top = expect!(toml.as_table(), "`analyzer.threshold` must be a table");
 
 		if let Some(value) = top.get("size") {
-			self.size = expect!(value.as_integer(), "`analyzer.beat.threshold.size` must be an integer") as usize;
+			let value = expect!(value.as_integer(), "`analyzer.beat.threshold.size` must be an integer");
+
+			if value < 1 {
+				expect!("`analyzer.beat.threshold.size` must be greater than zero");
+			}
+
+			self.size = value as usize;
 		}
 
 		if let Some(value) = top.get("sensitivity") {
 			self.sensitivity = expect!(value.as_float(), "`analyzer.beat.threshold.sensitivity` must be a float") as f64;
 		}
 
+		if let Some(value) = top.get("density") {
+			match value {
+				&Value::Float(density) if density > 0.0 =>
+					self.density = Some(density),
+
+				&Value::Boolean(false) =>
+					self.density = None,
+
+				_ =>
+					expect!("`analyzer.beat.threshold.density` must be a positive float or false"),
+			}
+		}
+
 		Ok(())
 	}
 }
@@ -347,4 +734,168 @@ impl Threshold {
 	pub fn sensitivity(&self) -> f64 {
 		self.sensitivity
 	}
+
+	/// The beats per second the sensitivity is calibrated for, if it's
+	/// calibrated at all.
+	#[inline(always)]
+	pub fn density(&self) -> Option<f64> {
+		self.density
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Tempo {
+	min:     f64,
+	max:     f64,
+	history: usize,
+}
+
+impl Default for Tempo {
+	fn default() -> Self {
+		Tempo {
+			min:     60.0,
+			max:     200.0,
+			history: 64,
+		}
+	}
+}
+
+impl Load for Tempo {
+	fn load(&mut self, args: &ArgvMap, toml: &Value) -> Result<(), ParserError> {
+		let top = expect!(toml.as_table(), "`analyzer.tempo` must be a table");
+
+		if let Some(value) = top.get("min") {
+			self.min = expect!(value.as_float(), "`analyzer.tempo.min` must be a float");
+		}
+
+		if let Some(value) = top.get("max") {
+			self.max = expect!(value.as_float(), "`analyzer.tempo.max` must be a float");
+		}
+
+		if let Some(value) = top.get("history") {
+			self.history = expect!(value.as_integer(), "`analyzer.tempo.history` must be an integer") as usize;
+		}
+
+		if self.min <= 0.0 || self.max < self.min * 2.0 {
+			expect!("`analyzer.tempo.max` must be at least twice `analyzer.tempo.min`");
+		}
+
+		Ok(())
+	}
+}
+
+impl Tempo {
+	/// The slowest tempo in beats per minute.
+	#[inline(always)]
+	pub fn min(&self) -> f64 {
+		self.min
+	}
+
+	/// The fastest tempo in beats per minute.
+	#[inline(always)]
+	pub fn max(&self) -> f64 {
+		self.max
+	}
+
+	/// How many onsets the estimation looks at.
+	#[inline(always)]
+	pub fn history(&self) -> usize {
+		self.history
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Bar {
+	beats: u32,
+}
+
+impl Default for Bar {
+	fn default() -> Self {
+		Bar {
+			beats: 4,
+		}
+	}
+}
+
+impl Load for Bar {
+	fn load(&mut self, args: &ArgvMap, toml: &Value) -> Result<(), ParserError> {
+		let top = expect!(toml.as_table(), "`analyzer.bar` must be a table");
+
+		if let Some(value) = top.get("beats") {
+			self.beats = expect!(value.as_integer(), "`analyzer.bar.beats` must be an integer") as u32;
+		}
+
+		if self.beats == 0 {
+			expect!("`analyzer.bar.beats` must be greater than zero");
+		}
+
+		Ok(())
+	}
+}
+
+impl Bar {
+	/// How many beats there are in a bar.
+	#[inline(always)]
+	pub fn beats(&self) -> u32 {
+		self.beats
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Calibrate {
+	duration: f64,
+	output:   Option<PathBuf>,
+}
+
+impl Default for Calibrate {
+	fn default() -> Self {
+		Calibrate {
+			duration: 30.0,
+			output:   None,
+		}
+	}
+}
+
+impl Load for Calibrate {
+	fn load(&mut self, args: &ArgvMap, toml: &Value) -> Result<(), ParserError> {
+		let top = expect!(toml.as_table(), "`analyzer.calibrate` must be a table");
+
+		if let Some(value) = top.get("duration") {
+			self.duration = expect!(value.as_float(), "`analyzer.calibrate.duration` must be a float");
+		}
+
+		if let Some(value) = top.get("output") {
+			match value {
+				&Value::String(ref path) =>
+					self.output = Some(PathBuf::from(path)),
+
+				&Value::Boolean(false) =>
+					self.output = None,
+
+				_ =>
+					expect!("`analyzer.calibrate.output` must be either a string or false"),
+			}
+		}
+
+		if self.duration <= 0.0 {
+			expect!("`analyzer.calibrate.duration` must be greater than zero");
+		}
+
+		Ok(())
+	}
+}
+
+impl Calibrate {
+	/// Seconds from the start of the file the calibration listens to, the
+	/// whole file is used with an offline analysis.
+	#[inline(always)]
+	pub fn duration(&self) -> f64 {
+		self.duration
+	}
+
+	/// Where the calibrated thresholds are written as TOML.
+	#[inline(always)]
+	pub fn output(&self) -> Option<&Path> {
+		self.output.as_ref().map(|p| p.as_ref())
+	}
 }
@@ -6,15 +6,17 @@ use settings::Load;
 
 #[derive(Clone, Debug)]
 pub struct Audio {
-	music: bool,
-	only:  bool,
+	music:   bool,
+	only:    bool,
+	latency: f64,
 }
 
 impl Default for Audio {
 	fn default() -> Audio {
 		Audio {
-			music: true,
-			only:  false,
+			music:   true,
+			only:    false,
+			latency: 0.0,
 		}
 	}
 }
@@ -33,6 +35,10 @@ impl Load for Audio {
 			if let Some(value) = toml.get("music") {
 				self.music = expect!(value.as_bool(), "`audio.music` must be a boolean");
 			}
+
+			if let Some(value) = toml.get("latency") {
+				self.latency = expect!(value.as_float(), "`audio.latency` must be a float");
+			}
 		}
 
 		if args.get_bool("--audio-only") {
@@ -57,4 +63,11 @@ impl Audio {
 	pub fn only(&self) -> bool {
 		self.only
 	}
+
+	/// Seconds the audio is heard after it's played, beats are moved later by
+	/// it.
+	#[inline(always)]
+	pub fn latency(&self) -> f64 {
+		self.latency
+	}
 }
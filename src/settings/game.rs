@@ -5,25 +5,43 @@ use toml::{Value, ParserError};
 use ffmpeg::Rational;
 use regex::Regex;
 
+use analyzer::LABELS;
 use game::ship::Shape;
-use util::Fill;
-use settings::Load;
+use game::enemy::{Path, Fire};
+use util::{Fill, Color};
+use settings::{Load, Pattern};
 
 #[derive(Clone, Debug)]
 pub struct Game {
-	step: f64,
-
-	window: Window,
-	ship:   Ship,
+	step:      f64,
+	autopause: bool,
+	telegraph: f64,
+
+	window:  Window,
+	player:  Player,
+	score:   Score,
+	ship:    Ship,
+	weapon:   Weapon,
+	enemies:  HashMap<String, Enemy>,
+	patterns: HashMap<String, Pattern>,
+	segments: HashMap<String, Segment>,
 }
 
 impl Default for Game {
 	fn default() -> Self {
 		Game {
-			step: 0.015,
-
-			window: Window::default(),
-			ship:   Ship::default(),
+			step:      0.015,
+			autopause: true,
+			telegraph: 0.0,
+
+			window:  Window::default(),
+			player:  Player::default(),
+			score:   Score::default(),
+			ship:    Ship::default(),
+			weapon:   Weapon::default(),
+			enemies:  HashMap::new(),
+			patterns: HashMap::new(),
+			segments: HashMap::new(),
 		}
 	}
 }
@@ -39,13 +57,70 @@ impl Load for Game {
 				self.step = expect!(value.as_float(), "`game.step` must be a float");
 			}
 
+			if let Some(value) = toml.get("autopause") {
+				self.autopause = expect!(value.as_bool(), "`game.autopause` must be a boolean");
+			}
+
+			if let Some(value) = toml.get("telegraph") {
+				self.telegraph = expect!(value.as_float(), "`game.telegraph` must be a float");
+			}
+
 			if let Some(toml) = toml.get("window") {
 				try!(self.window.load(args, toml));
 			}
 
+			if let Some(toml) = toml.get("player") {
+				try!(self.player.load(args, toml));
+			}
+
+			if let Some(toml) = toml.get("score") {
+				try!(self.score.load(args, toml));
+			}
+
 			if let Some(toml) = toml.get("ship") {
 				try!(self.ship.load(args, toml));
 			}
+
+			if let Some(toml) = toml.get("weapon") {
+				try!(self.weapon.load(args, toml));
+			}
+
+			if let Some(toml) = toml.get("enemy") {
+				let toml = expect!(toml.as_table(), "`game.enemy` must be a table");
+
+				for (name, toml) in toml {
+					let mut enemy = self.enemies.get(name).cloned().unwrap_or_else(Enemy::default);
+					try!(enemy.load(args, toml));
+
+					self.enemies.insert(name.clone(), enemy);
+				}
+			}
+
+			if let Some(toml) = toml.get("pattern") {
+				let toml = expect!(toml.as_table(), "`game.pattern` must be a table");
+
+				for (name, toml) in toml {
+					let mut pattern = self.patterns.get(name).cloned().unwrap_or_else(Pattern::default);
+					try!(pattern.load(args, toml));
+
+					self.patterns.insert(name.clone(), pattern);
+				}
+			}
+
+			if let Some(toml) = toml.get("segment") {
+				let toml = expect!(toml.as_table(), "`game.segment` must be a table");
+
+				for (label, toml) in toml {
+					if !LABELS.contains(&&label[..]) {
+						expect!(format!("`game.segment.{}` must be one of {}", label, LABELS.join(", ")));
+					}
+
+					let mut segment = self.segments.get(label).cloned().unwrap_or_else(Segment::default);
+					try!(segment.load(args, toml));
+
+					self.segments.insert(label.clone(), segment);
+				}
+			}
 		}
 
 		Ok(())
@@ -53,20 +128,117 @@ impl Load for Game {
 }
 
 impl Game {
+	/// Makes sure the patterns used by enemies, segments and other patterns
+	/// exist, and that patterns don't end up running themselves.
+	///
+	/// It can only be done once all the settings have been merged.
+	pub fn check(&self) -> Result<(), ParserError> {
+		for (name, enemy) in &self.enemies {
+			if let Fire::Pattern { name: ref pattern, .. } = enemy.fire {
+				if !self.patterns.contains_key(pattern) {
+					expect!(format!("`game.enemy.{}.fire` must be 'down' or 'aimed' or a pattern, `{}` is not", name, pattern));
+				}
+			}
+		}
+
+		for (label, segment) in &self.segments {
+			if let Some(ref pattern) = segment.pattern {
+				if !self.patterns.contains_key(pattern) {
+					expect!(format!("`game.segment.{}.pattern` must be a pattern, `{}` is not", label, pattern));
+				}
+			}
+		}
+
+		for (name, pattern) in &self.patterns {
+			for child in pattern.children() {
+				if !self.patterns.contains_key(child) {
+					expect!(format!("`game.pattern.{}.pattern` must be a pattern, `{}` is not", name, child));
+				}
+			}
+		}
+
+		for name in self.patterns.keys() {
+			if self.cycles(name, &mut Vec::new()) {
+				expect!(format!("`game.pattern.{}` ends up running itself", name));
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Whether the named pattern runs one of the patterns on the path to it.
+	fn cycles<'a>(&'a self, name: &'a str, path: &mut Vec<&'a str>) -> bool {
+		if path.contains(&name) {
+			return true;
+		}
+
+		path.push(name);
+
+		let result = self.patterns.get(name).map(|pattern|
+			pattern.children().into_iter().any(|child| self.cycles(child, path))).unwrap_or(false);
+
+		path.pop();
+
+		result
+	}
+
 	#[inline(always)]
 	pub fn step(&self) -> f64 {
 		self.step
 	}
 
+	/// Whether the game pauses when the window loses focus.
+	#[inline(always)]
+	pub fn autopause(&self) -> bool {
+		self.autopause
+	}
+
+	/// How many seconds before their beat enemy waves show up.
+	#[inline(always)]
+	pub fn telegraph(&self) -> f64 {
+		self.telegraph
+	}
+
 	#[inline(always)]
 	pub fn window(&self) -> &Window {
 		&self.window
 	}
 
+	#[inline(always)]
+	pub fn player(&self) -> &Player {
+		&self.player
+	}
+
+	#[inline(always)]
+	pub fn score(&self) -> &Score {
+		&self.score
+	}
+
 	#[inline(always)]
 	pub fn ship(&self) -> &Ship {
 		&self.ship
 	}
+
+	#[inline(always)]
+	pub fn weapon(&self) -> &Weapon {
+		&self.weapon
+	}
+
+	#[inline(always)]
+	pub fn enemy(&self, name: &str) -> Option<&Enemy> {
+		self.enemies.get(name)
+	}
+
+	#[inline(always)]
+	pub fn pattern(&self, name: &str) -> Option<&Pattern> {
+		self.patterns.get(name)
+	}
+
+	/// The settings for the song segments with the given label.
+	#[inline(always)]
+	pub fn segment(&self, label: &str) -> Option<&Segment> {
+		self.segments.get(label)
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -101,11 +273,21 @@ impl Load for Window {
 		}
 
 		for (key, toml) in toml {
-			if Regex::new(r"(\d+)-(\d+)").unwrap().is_match(key) {
-				let mut window = Window::default();
+			if let Some(c) = Regex::new(r"^(\d+)-(\d+)$").unwrap().captures(key) {
+				let n = expect!(c.at(1).unwrap().parse::<i32>().ok(), "`game.window.N-M` must have a valid numerator");
+				let d = expect!(c.at(2).unwrap().parse::<i32>().ok(), "`game.window.N-M` must have a valid denominator");
+
+				if n == 0 || d == 0 {
+					expect!("`game.window.N-M` cannot have zeroes");
+				}
+
+				// Normalize the key so `32-18` and `16-9` are the same aspect.
+				let key = aspect(Rational::new(n, d));
+
+				let mut window = self.aspects.get(&key).cloned().unwrap_or_else(Window::default);
 				try!(window.load(args, toml));
 
-				self.aspects.insert(key.clone(), window);
+				self.aspects.insert(key, window);
 			}
 		}
 
@@ -115,8 +297,8 @@ impl Load for Window {
 
 impl Window {
 	#[inline(always)]
-	pub fn aspect(&self, aspect: Rational) -> Option<&Window> {
-		self.aspects.get(&format!("{}-{}", aspect.numerator(), aspect.denominator()))
+	pub fn aspect(&self, value: Rational) -> Option<&Window> {
+		self.aspects.get(&aspect(value))
 	}
 
 	#[inline(always)]
@@ -130,6 +312,175 @@ impl Window {
 	}
 }
 
+#[derive(Clone, Debug)]
+pub struct Player {
+	lives:           u8,
+	respawn:         f64,
+	invulnerability: f64,
+}
+
+impl Default for Player {
+	fn default() -> Player {
+		Player {
+			lives:           3,
+			respawn:         1.0,
+			invulnerability: 2.0,
+		}
+	}
+}
+
+impl Load for Player {
+	fn load(&mut self, args: &ArgvMap, toml: &Value) -> Result<(), ParserError> {
+		let toml = expect!(toml.as_table(), "`game.player` must be a table");
+
+		if let Some(value) = toml.get("lives") {
+			match expect!(value.as_integer(), "`game.player.lives` must be an integer") {
+				lives if lives >= 1 && lives <= 99 =>
+					self.lives = lives as u8,
+
+				_ =>
+					expect!("`game.player.lives` must be between 1 and 99"),
+			}
+		}
+
+		if let Some(value) = toml.get("respawn") {
+			self.respawn = expect!(value.as_float(), "`game.player.respawn` must be a float");
+		}
+
+		if let Some(value) = toml.get("invulnerability") {
+			self.invulnerability = expect!(value.as_float(), "`game.player.invulnerability` must be a float");
+		}
+
+		Ok(())
+	}
+}
+
+impl Player {
+	#[inline(always)]
+	pub fn lives(&self) -> u8 {
+		self.lives
+	}
+
+	/// Seconds between death and respawn.
+	#[inline(always)]
+	pub fn respawn(&self) -> f64 {
+		self.respawn
+	}
+
+	/// Seconds of invulnerability after a respawn.
+	#[inline(always)]
+	pub fn invulnerability(&self) -> f64 {
+		self.invulnerability
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Score {
+	window:     f64,
+	kill:       u64,
+	dodge:      u64,
+	combo:      u32,
+	multiplier: u32,
+	decay:      f64,
+}
+
+impl Default for Score {
+	fn default() -> Score {
+		Score {
+			window:     0.1,
+			kill:       100,
+			dodge:      10,
+			combo:      4,
+			multiplier: 8,
+			decay:      0.5,
+		}
+	}
+}
+
+impl Load for Score {
+	fn load(&mut self, args: &ArgvMap, toml: &Value) -> Result<(), ParserError> {
+		let toml = expect!(toml.as_table(), "`game.score` must be a table");
+
+		if let Some(value) = toml.get("window") {
+			self.window = expect!(value.as_float(), "`game.score.window` must be a float");
+		}
+
+		if let Some(value) = toml.get("kill") {
+			self.kill = expect!(value.as_integer(), "`game.score.kill` must be an integer") as u64;
+		}
+
+		if let Some(value) = toml.get("dodge") {
+			self.dodge = expect!(value.as_integer(), "`game.score.dodge` must be an integer") as u64;
+		}
+
+		if let Some(value) = toml.get("combo") {
+			self.combo = expect!(value.as_integer(), "`game.score.combo` must be an integer") as u32;
+
+			if self.combo == 0 {
+				expect!("`game.score.combo` must be greater than zero");
+			}
+		}
+
+		if let Some(value) = toml.get("multiplier") {
+			match expect!(value.as_integer(), "`game.score.multiplier` must be an integer") {
+				multiplier if multiplier >= 1 && multiplier <= 99 =>
+					self.multiplier = multiplier as u32,
+
+				_ =>
+					expect!("`game.score.multiplier` must be between 1 and 99"),
+			}
+		}
+
+		if let Some(value) = toml.get("decay") {
+			self.decay = expect!(value.as_float(), "`game.score.decay` must be a float");
+
+			if self.decay < 0.0 || self.decay > 1.0 {
+				expect!("`game.score.decay` must be between 0.0 and 1.0");
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl Score {
+	/// Seconds around a beat an action is considered on-beat.
+	#[inline(always)]
+	pub fn window(&self) -> f64 {
+		self.window
+	}
+
+	/// Points for a kill.
+	#[inline(always)]
+	pub fn kill(&self) -> u64 {
+		self.kill
+	}
+
+	/// Points for a dodge.
+	#[inline(always)]
+	pub fn dodge(&self) -> u64 {
+		self.dodge
+	}
+
+	/// On-beat actions needed to increase the multiplier.
+	#[inline(always)]
+	pub fn combo(&self) -> u32 {
+		self.combo
+	}
+
+	/// The maximum multiplier.
+	#[inline(always)]
+	pub fn multiplier(&self) -> u32 {
+		self.multiplier
+	}
+
+	/// How much of the combo is kept when going off-beat.
+	#[inline(always)]
+	pub fn decay(&self) -> f64 {
+		self.decay
+	}
+}
+
 #[derive(Clone, Debug)]
 pub struct Ship {
 	shape:  Shape,
@@ -154,19 +505,7 @@ impl Load for Ship {
 		if let Some(value) = toml.get("shape") {
 			let value = expect!(value.as_str(), "`game.ship.shape` must be a string");
 
-			self.shape = match value {
-				"cube" =>
-					Shape::Cube,
-				
-				"tetrahedron" =>
-					Shape::Tetrahedron,
-
-				"octahedron" =>
-					Shape::Octahedron,
-
-				_ =>
-					expect!("`game.ship.shape` must be 'cube' or 'tetrahedron' or 'octahedron'"),
-			}
+			self.shape = expect!(shape(value), "`game.ship.shape` must be 'cube' or 'tetrahedron' or 'octahedron'");
 		}
 
 		if let Some(value) = toml.get("face") {
@@ -211,3 +550,400 @@ impl Ship {
 		self.border.clone()
 	}
 }
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Kind {
+	Plasma,
+	Ray,
+}
+
+#[derive(Clone, Debug)]
+pub struct Weapon {
+	kind:     Kind,
+	rate:     f64,
+	sync:     bool,
+	speed:    f32,
+	size:     f32,
+	duration: f64,
+	color:    Option<Color>,
+}
+
+impl Default for Weapon {
+	fn default() -> Weapon {
+		Weapon {
+			kind:     Kind::Plasma,
+			rate:     0.15,
+			sync:     false,
+			speed:    4.0,
+			size:     1.5,
+			duration: 0.1,
+			color:    None,
+		}
+	}
+}
+
+impl Load for Weapon {
+	fn load(&mut self, args: &ArgvMap, toml: &Value) -> Result<(), ParserError> {
+		let toml = expect!(toml.as_table(), "`game.weapon` must be a table");
+
+		if let Some(value) = toml.get("kind") {
+			self.kind = match expect!(value.as_str(), "`game.weapon.kind` must be a string") {
+				"plasma" =>
+					Kind::Plasma,
+
+				"ray" =>
+					Kind::Ray,
+
+				_ =>
+					expect!("`game.weapon.kind` must be 'plasma' or 'ray'"),
+			};
+		}
+
+		if let Some(value) = toml.get("rate") {
+			self.rate = expect!(value.as_float(), "`game.weapon.rate` must be a float");
+		}
+
+		if let Some(value) = toml.get("sync") {
+			self.sync = expect!(value.as_bool(), "`game.weapon.sync` must be a boolean");
+		}
+
+		if let Some(value) = toml.get("speed") {
+			self.speed = expect!(value.as_float(), "`game.weapon.speed` must be a float") as f32;
+		}
+
+		if let Some(value) = toml.get("size") {
+			self.size = expect!(value.as_float(), "`game.weapon.size` must be a float") as f32;
+		}
+
+		if let Some(value) = toml.get("duration") {
+			self.duration = expect!(value.as_float(), "`game.weapon.duration` must be a float");
+		}
+
+		if let Some(value) = toml.get("color") {
+			self.color = Some(Color::from(expect!(value.as_str(), "`game.weapon.color` must be a string")));
+		}
+
+		Ok(())
+	}
+}
+
+impl Weapon {
+	#[inline(always)]
+	pub fn kind(&self) -> Kind {
+		self.kind
+	}
+
+	/// Seconds between shots.
+	#[inline(always)]
+	pub fn rate(&self) -> f64 {
+		self.rate
+	}
+
+	/// Whether shots are locked to the beats instead of the rate.
+	#[inline(always)]
+	pub fn sync(&self) -> bool {
+		self.sync
+	}
+
+	#[inline(always)]
+	pub fn speed(&self) -> f32 {
+		self.speed
+	}
+
+	/// The radius of plasma or the width of rays.
+	#[inline(always)]
+	pub fn size(&self) -> f32 {
+		self.size
+	}
+
+	/// How long rays last.
+	#[inline(always)]
+	pub fn duration(&self) -> f64 {
+		self.duration
+	}
+
+	#[inline(always)]
+	pub fn color(&self) -> Option<Color> {
+		self.color
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Enemy {
+	shape:  Shape,
+	face:   Fill,
+	border: Option<Fill>,
+	scale:  f32,
+
+	count: u32,
+	speed: f32,
+	path:  Path,
+	fire:  Fire,
+}
+
+impl Default for Enemy {
+	fn default() -> Enemy {
+		Enemy {
+			shape:  Shape::Octahedron,
+			face:   Fill::from("#000"),
+			border: Some(Fill::from("#fff")),
+			scale:  0.8,
+
+			count: 1,
+			speed: 1.0,
+			path:  Path::Straight,
+			fire:  Fire::None,
+		}
+	}
+}
+
+impl Load for Enemy {
+	fn load(&mut self, args: &ArgvMap, toml: &Value) -> Result<(), ParserError> {
+		let toml = expect!(toml.as_table(), "`game.enemy.*` must be a table");
+
+		if let Some(value) = toml.get("shape") {
+			let value = expect!(value.as_str(), "`game.enemy.*.shape` must be a string");
+
+			self.shape = expect!(shape(value), "`game.enemy.*.shape` must be 'cube' or 'tetrahedron' or 'octahedron'");
+		}
+
+		if let Some(value) = toml.get("face") {
+			self.face = Fill::from(expect!(value.as_str(), "`game.enemy.*.face` must be a string"));
+		}
+
+		if let Some(value) = toml.get("border") {
+			match value {
+				&Value::String(ref value) =>
+					self.border = Some(Fill::from(value)),
+
+				&Value::Boolean(false) =>
+					self.border = None,
+
+				&Value::Boolean(true) =>
+					(),
+
+				_ =>
+					expect!("`game.enemy.*.border` must be a string or boolean"),
+			}
+		}
+
+		if let Some(value) = toml.get("scale") {
+			self.scale = expect!(value.as_float(), "`game.enemy.*.scale` must be a float") as f32;
+		}
+
+		if let Some(value) = toml.get("count") {
+			match expect!(value.as_integer(), "`game.enemy.*.count` must be an integer") {
+				count if count >= 1 && count <= 32 =>
+					self.count = count as u32,
+
+				_ =>
+					expect!("`game.enemy.*.count` must be between 1 and 32"),
+			}
+		}
+
+		if let Some(value) = toml.get("speed") {
+			self.speed = expect!(value.as_float(), "`game.enemy.*.speed` must be a float") as f32;
+		}
+
+		if let Some(value) = toml.get("path") {
+			let value = expect!(value.as_str(), "`game.enemy.*.path` must be a string");
+
+			self.path = match value {
+				"straight" =>
+					Path::Straight,
+
+				"sine" =>
+					Path::Sine {
+						amplitude: 30.0,
+						frequency: 0.5,
+					},
+
+				"dive" =>
+					Path::Dive,
+
+				"sweep" =>
+					Path::Sweep,
+
+				_ =>
+					expect!("`game.enemy.*.path` must be 'straight' or 'sine' or 'dive' or 'sweep'"),
+			};
+		}
+
+		if let Path::Sine { ref mut amplitude, ref mut frequency } = self.path {
+			if let Some(value) = toml.get("amplitude") {
+				*amplitude = expect!(value.as_float(), "`game.enemy.*.amplitude` must be a float") as f32;
+			}
+
+			if let Some(value) = toml.get("frequency") {
+				*frequency = expect!(value.as_float(), "`game.enemy.*.frequency` must be a float") as f32;
+			}
+		}
+
+		if let Some(value) = toml.get("fire") {
+			match value {
+				&Value::String(ref value) =>
+					self.fire = match value.as_ref() {
+						"down" =>
+							Fire::Down { rate: 1.0 },
+
+						"aimed" =>
+							Fire::Aimed { rate: 1.0 },
+
+						// Anything else is the name of a pattern.
+						name =>
+							Fire::Pattern { name: name.to_owned(), rate: 1.0 },
+					},
+
+				&Value::Boolean(false) =>
+					self.fire = Fire::None,
+
+				_ =>
+					expect!("`game.enemy.*.fire` must be a string or false"),
+			}
+		}
+
+		if let Some(value) = toml.get("rate") {
+			let value = expect!(value.as_float(), "`game.enemy.*.rate` must be a float");
+
+			match self.fire {
+				Fire::Down { ref mut rate } | Fire::Aimed { ref mut rate } | Fire::Pattern { ref mut rate, .. } =>
+					*rate = value,
+
+				Fire::None =>
+					(),
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl Enemy {
+	#[inline(always)]
+	pub fn shape(&self) -> Shape {
+		self.shape
+	}
+
+	#[inline(always)]
+	pub fn face(&self) -> Fill {
+		self.face.clone()
+	}
+
+	#[inline(always)]
+	pub fn border(&self) -> Option<Fill> {
+		self.border.clone()
+	}
+
+	#[inline(always)]
+	pub fn scale(&self) -> f32 {
+		self.scale
+	}
+
+	#[inline(always)]
+	pub fn count(&self) -> u32 {
+		self.count
+	}
+
+	#[inline(always)]
+	pub fn speed(&self) -> f32 {
+		self.speed
+	}
+
+	#[inline(always)]
+	pub fn path(&self) -> Path {
+		self.path
+	}
+
+	#[inline(always)]
+	pub fn fire(&self) -> Fire {
+		self.fire.clone()
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Segment {
+	difficulty: f32,
+	pattern:    Option<String>,
+	palette:    Vec<Color>,
+}
+
+impl Default for Segment {
+	fn default() -> Segment {
+		Segment {
+			difficulty: 1.0,
+			pattern:    None,
+			palette:    Vec::new(),
+		}
+	}
+}
+
+impl Load for Segment {
+	fn load(&mut self, args: &ArgvMap, toml: &Value) -> Result<(), ParserError> {
+		let toml = expect!(toml.as_table(), "`game.segment.*` must be a table");
+
+		if let Some(value) = toml.get("difficulty") {
+			self.difficulty = expect!(value.as_float(), "`game.segment.*.difficulty` must be a float") as f32;
+
+			if self.difficulty <= 0.0 {
+				expect!("`game.segment.*.difficulty` must be greater than zero");
+			}
+		}
+
+		if let Some(value) = toml.get("pattern") {
+			self.pattern = Some(expect!(value.as_str(), "`game.segment.*.pattern` must be a string").to_owned());
+		}
+
+		if let Some(value) = toml.get("palette") {
+			self.palette.clear();
+
+			for value in expect!(value.as_slice(), "`game.segment.*.palette` must be an array") {
+				self.palette.push(Color::from(expect!(value.as_str(), "`game.segment.*.palette.*` must be a string")));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl Segment {
+	/// How much faster enemies move and fire.
+	#[inline(always)]
+	pub fn difficulty(&self) -> f32 {
+		self.difficulty
+	}
+
+	/// The pattern fired on beats of bands without an enemy or pattern,
+	/// instead of `default`.
+	#[inline(always)]
+	pub fn pattern(&self) -> Option<&str> {
+		self.pattern.as_ref().map(|n| n.as_ref())
+	}
+
+	/// The colors used for the bands in order, instead of their own.
+	#[inline(always)]
+	pub fn palette(&self) -> &[Color] {
+		&self.palette
+	}
+}
+
+fn aspect(value: Rational) -> String {
+	let value = value.reduce();
+
+	format!("{}-{}", value.numerator(), value.denominator())
+}
+
+fn shape(value: &str) -> Option<Shape> {
+	match value {
+		"cube" =>
+			Some(Shape::Cube),
+
+		"tetrahedron" =>
+			Some(Shape::Tetrahedron),
+
+		"octahedron" =>
+			Some(Shape::Octahedron),
+
+		_ =>
+			None,
+	}
+}
@@ -37,7 +37,10 @@ pub trait Load {
 }
 
 pub mod game;
-pub use self::game::Game;
+pub use self::game::{Game, Weapon};
+
+pub mod pattern;
+pub use self::pattern::Pattern;
 
 pub mod analyzer;
 pub use self::analyzer::Analyzer;
@@ -51,6 +54,7 @@ pub use self::video::Video;
 #[derive(Clone, Default, Debug)]
 pub struct Settings {
 	input: Option<String>,
+	start: f64,
 
 	game:     Game,
 	analyzer: Analyzer,
@@ -65,12 +69,33 @@ impl Settings {
 
 		settings.input = Some(String::from(args.get_str("<input>")));
 
+		if !args.get_str("--start").is_empty() {
+			settings.start = expect!(time(args.get_str("--start")),
+				"`--start` must be a time like `92.5`, `1:32` or `1:01:32`");
+		}
+
 		if !files.is_empty() {
 			for file in &files {
 				try!(settings.merge(args, &file));
 			}
 		}
 
+		try!(settings.game.check());
+
+		for band in settings.analyzer.beat().bands() {
+			if let Some(pattern) = band.pattern() {
+				if settings.game.pattern(pattern).is_none() {
+					expect!(format!("`analyzer.beat.band.*.pattern` must be a pattern, `{}` is not", pattern));
+				}
+			}
+
+			if let Some(enemy) = band.enemy() {
+				if settings.game.enemy(enemy).is_none() {
+					expect!(format!("`analyzer.beat.band.*.enemy` must be an enemy, `{}` is not", enemy));
+				}
+			}
+		}
+
 		Ok(settings)
 	}
 
@@ -102,6 +127,11 @@ impl Settings {
 		self.input.as_ref().unwrap()
 	}
 
+	/// The time in seconds to start playing from.
+	pub fn start(&self) -> f64 {
+		self.start
+	}
+
 	pub fn game(&self) -> &Game {
 		&self.game
 	}
@@ -118,3 +148,26 @@ impl Settings {
 		&self.analyzer
 	}
 }
+
+/// Parses a time in the `[[hours:]minutes:]seconds` format.
+fn time(string: &str) -> Option<f64> {
+	let mut result = 0.0;
+
+	for (index, part) in string.split(':').enumerate() {
+		if index > 2 {
+			return None;
+		}
+
+		let value = match part.parse::<f64>() {
+			Ok(value) if value >= 0.0 =>
+				value,
+
+			_ =>
+				return None,
+		};
+
+		result = result * 60.0 + value;
+	}
+
+	Some(result)
+}
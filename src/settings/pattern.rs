@@ -0,0 +1,293 @@
+use docopt::ArgvMap;
+use toml::{Value, ParserError};
+
+use util::Color;
+use settings::Load;
+
+/// A bullet pattern, angles are in degrees with 0 going right and 90 going down
+/// the playfield.
+#[derive(Clone, Debug)]
+pub struct Pattern {
+	kind: Kind,
+
+	angle:  f32,
+	steer:  f32,
+	speed:  f32,
+	size:   f32,
+	color:  Option<Color>,
+	origin: Option<(f32, f32)>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Kind {
+	/// Shots spread evenly over `spread` degrees around the angle.
+	Fan {
+		count:  u32,
+		spread: f32,
+	},
+
+	/// Shots spread evenly all around.
+	Ring {
+		count: u32,
+	},
+
+	/// Shots fired one after the other, turning `turns` times around.
+	Spiral {
+		count: u32,
+		turns: f32,
+		delay: f64,
+	},
+
+	/// Shots fired one after the other towards the player.
+	Aimed {
+		count: u32,
+		delay: f64,
+	},
+
+	/// Another pattern run `count` times, rotated by `rotate` degrees every time.
+	Repeat {
+		count:   u32,
+		delay:   f64,
+		rotate:  f32,
+		pattern: Box<Child>,
+	},
+}
+
+/// The pattern a repeat runs, either by name or inline.
+#[derive(Clone, Debug)]
+pub enum Child {
+	Named(String),
+	Inline(Pattern),
+}
+
+impl Default for Pattern {
+	fn default() -> Pattern {
+		Pattern {
+			kind: Kind::Ring { count: 8 },
+
+			angle:  90.0,
+			steer:  0.0,
+			speed:  1.5,
+			size:   1.5,
+			color:  None,
+			origin: None,
+		}
+	}
+}
+
+impl Load for Pattern {
+	fn load(&mut self, args: &ArgvMap, toml: &Value) -> Result<(), ParserError> {
+		let toml = expect!(toml.as_table(), "`game.pattern.*` must be a table");
+
+		if let Some(value) = toml.get("kind") {
+			self.kind = match expect!(value.as_str(), "`game.pattern.*.kind` must be a string") {
+				"fan" =>
+					Kind::Fan { count: 5, spread: 60.0 },
+
+				"ring" =>
+					Kind::Ring { count: 8 },
+
+				"spiral" =>
+					Kind::Spiral { count: 16, turns: 1.0, delay: 0.05 },
+
+				"aimed" =>
+					Kind::Aimed { count: 3, delay: 0.1 },
+
+				"repeat" =>
+					Kind::Repeat { count: 2, delay: 0.25, rotate: 0.0, pattern: Box::new(Child::Inline(Pattern::default())) },
+
+				_ =>
+					expect!("`game.pattern.*.kind` must be 'fan' or 'ring' or 'spiral' or 'aimed' or 'repeat'"),
+			};
+		}
+
+		if let Some(value) = toml.get("count") {
+			let value = expect!(value.as_integer(), "`game.pattern.*.count` must be an integer");
+
+			if value < 1 || value > 256 {
+				expect!("`game.pattern.*.count` must be between 1 and 256");
+			}
+
+			let value = value as u32;
+
+			match self.kind {
+				Kind::Fan { ref mut count, .. } |
+				Kind::Ring { ref mut count } |
+				Kind::Spiral { ref mut count, .. } |
+				Kind::Aimed { ref mut count, .. } |
+				Kind::Repeat { ref mut count, .. } =>
+					*count = value,
+			}
+		}
+
+		if let Some(value) = toml.get("delay") {
+			let value = expect!(value.as_float(), "`game.pattern.*.delay` must be a float");
+
+			match self.kind {
+				Kind::Spiral { ref mut delay, .. } |
+				Kind::Aimed { ref mut delay, .. } |
+				Kind::Repeat { ref mut delay, .. } =>
+					*delay = value,
+
+				_ =>
+					expect!("`game.pattern.*.delay` is only valid for 'spiral', 'aimed' and 'repeat'"),
+			}
+		}
+
+		if let Some(value) = toml.get("spread") {
+			let value = expect!(value.as_float(), "`game.pattern.*.spread` must be a float") as f32;
+
+			match self.kind {
+				Kind::Fan { ref mut spread, .. } =>
+					*spread = value,
+
+				_ =>
+					expect!("`game.pattern.*.spread` is only valid for 'fan'"),
+			}
+		}
+
+		if let Some(value) = toml.get("turns") {
+			let value = expect!(value.as_float(), "`game.pattern.*.turns` must be a float") as f32;
+
+			match self.kind {
+				Kind::Spiral { ref mut turns, .. } =>
+					*turns = value,
+
+				_ =>
+					expect!("`game.pattern.*.turns` is only valid for 'spiral'"),
+			}
+		}
+
+		if let Some(value) = toml.get("rotate") {
+			let value = expect!(value.as_float(), "`game.pattern.*.rotate` must be a float") as f32;
+
+			match self.kind {
+				Kind::Repeat { ref mut rotate, .. } =>
+					*rotate = value,
+
+				_ =>
+					expect!("`game.pattern.*.rotate` is only valid for 'repeat'"),
+			}
+		}
+
+		if let Some(value) = toml.get("pattern") {
+			let child = match value {
+				&Value::String(ref name) =>
+					Child::Named(name.clone()),
+
+				&Value::Table(..) => {
+					let mut pattern = Pattern::default();
+					try!(pattern.load(args, value));
+
+					Child::Inline(pattern)
+				},
+
+				_ =>
+					expect!("`game.pattern.*.pattern` must be a string or a table"),
+			};
+
+			match self.kind {
+				Kind::Repeat { ref mut pattern, .. } =>
+					*pattern = Box::new(child),
+
+				_ =>
+					expect!("`game.pattern.*.pattern` is only valid for 'repeat'"),
+			}
+		}
+
+		if let Some(value) = toml.get("angle") {
+			self.angle = expect!(value.as_float(), "`game.pattern.*.angle` must be a float") as f32;
+		}
+
+		if let Some(value) = toml.get("steer") {
+			self.steer = expect!(value.as_float(), "`game.pattern.*.steer` must be a float") as f32;
+		}
+
+		if let Some(value) = toml.get("speed") {
+			self.speed = expect!(value.as_float(), "`game.pattern.*.speed` must be a float") as f32;
+		}
+
+		if let Some(value) = toml.get("size") {
+			self.size = expect!(value.as_float(), "`game.pattern.*.size` must be a float") as f32;
+		}
+
+		if let Some(value) = toml.get("color") {
+			self.color = Some(Color::from(expect!(value.as_str(), "`game.pattern.*.color` must be a string")));
+		}
+
+		if let Some(value) = toml.get("origin") {
+			let value = expect!(value.as_slice(), "`game.pattern.*.origin` must be an array");
+
+			if value.len() != 2 {
+				expect!("`game.pattern.*.origin` must be an array of two elements");
+			}
+
+			let x = expect!(value[0].as_float(), "`game.pattern.*.origin.0` must be a float") as f32;
+			let y = expect!(value[1].as_float(), "`game.pattern.*.origin.1` must be a float") as f32;
+
+			self.origin = Some((x, y));
+		}
+
+		Ok(())
+	}
+}
+
+impl Pattern {
+	/// The names of the patterns this one runs, inline ones included.
+	pub fn children(&self) -> Vec<&str> {
+		match self.kind {
+			Kind::Repeat { ref pattern, .. } =>
+				match **pattern {
+					Child::Named(ref name) =>
+						vec![name.as_ref()],
+
+					Child::Inline(ref pattern) =>
+						pattern.children(),
+				},
+
+			_ =>
+				Vec::new(),
+		}
+	}
+
+	#[inline(always)]
+	pub fn kind(&self) -> &Kind {
+		&self.kind
+	}
+
+	/// The base direction in degrees.
+	#[inline(always)]
+	pub fn angle(&self) -> f32 {
+		self.angle
+	}
+
+	/// How many degrees the pattern turns for every semitone the melody is
+	/// above A4 when it starts.
+	#[inline(always)]
+	pub fn steer(&self) -> f32 {
+		self.steer
+	}
+
+	#[inline(always)]
+	pub fn speed(&self) -> f32 {
+		self.speed
+	}
+
+	/// The radius of the shots.
+	#[inline(always)]
+	pub fn size(&self) -> f32 {
+		self.size
+	}
+
+	#[inline(always)]
+	pub fn color(&self) -> Option<Color> {
+		self.color
+	}
+
+	/// Where the pattern starts when not fired by an enemy, as a fraction of the
+	/// playfield size.
+	#[inline(always)]
+	pub fn origin(&self) -> Option<(f32, f32)> {
+		self.origin
+	}
+}
@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use ffmpeg::frame;
 use openal::{Error, Listener};
 use openal::source::{self, Stream};
@@ -10,6 +12,8 @@ pub struct Sound<'a> {
 
 	music:     Option<Stream<'a>>,
 	timestamp: i64,
+	buffers:   VecDeque<f64>,
+	played:    f64,
 
 	listener: Listener<'a>,
 }
@@ -21,24 +25,31 @@ impl<'a> Sound<'a> {
 
 			music:     None,
 			timestamp: -1,
+			buffers:   VecDeque::new(),
+			played:    0.0,
 
 			listener: try!(Listener::default(&Default::default())),
 		})
 	}
 
 	pub fn play<'b>(&'b mut self, frame: &frame::Audio) {
-		if let None = self.music {
-			self.music = Some(self.listener.source().unwrap().stream());
-		}
-
 		if self.timestamp >= frame.timestamp().unwrap() {
 			return;
 		}
 
 		self.timestamp = frame.timestamp().unwrap();
+		self.queue(frame.channels(), frame.plane::<i16>(0), frame.rate());
+	}
+
+	/// Queues the given samples on the music stream, starting it if needed.
+	pub fn queue(&mut self, channels: u16, samples: &[i16], rate: u32) {
+		if let None = self.music {
+			self.music = Some(self.listener.source().unwrap().stream());
+		}
 
 		if let Some(source) = self.music.as_mut() {
-			source.push(frame.channels(), frame.plane::<i16>(0), frame.rate()).unwrap();
+			source.push(channels, samples, rate).unwrap();
+			self.buffers.push_back(samples.len() as f64 / channels as f64 / rate as f64);
 
 			if source.state() != source::State::Playing {
 				source.play();
@@ -46,6 +57,43 @@ impl<'a> Sound<'a> {
 		}
 	}
 
+	/// Seconds of music played so far, `None` while the music isn't playing.
+	///
+	/// The offset of the stream is only within the buffers still queued, so the
+	/// length of the ones it already unqueued is kept aside and added to it.
+	pub fn position(&mut self) -> Option<f64> {
+		match self.music.as_ref() {
+			Some(source) if source.state() == source::State::Playing => {
+				while self.buffers.len() > source.queued() {
+					self.played += self.buffers.pop_front().unwrap();
+				}
+
+				Some(self.played + source.offset())
+			},
+
+			_ =>
+				None,
+		}
+	}
+
+	/// Pauses the music.
+	pub fn pause(&mut self) {
+		if let Some(source) = self.music.as_mut() {
+			if source.state() == source::State::Playing {
+				source.pause();
+			}
+		}
+	}
+
+	/// Resumes the music.
+	pub fn resume(&mut self) {
+		if let Some(source) = self.music.as_mut() {
+			if source.state() == source::State::Paused {
+				source.play();
+			}
+		}
+	}
+
 	pub fn render(&mut self, state: &State) {
 
 	}
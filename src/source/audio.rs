@@ -138,6 +138,11 @@ impl Audio {
 		self.start = time;
 	}
 
+	/// Gets the time in seconds of the given frame.
+	pub fn time(&self, frame: &frame::Audio) -> f64 {
+		frame.timestamp().unwrap_or(0) as f64 * self.details.time_base
+	}
+
 	/// Fetches the next audio frame.
 	///
 	/// Returns `None` on EOF.
@@ -35,7 +35,10 @@ pub enum Reader {
 }
 
 /// Spawns a packet reader, an audio decoder and a video decoder.
-pub fn spawn(path: &str, no_video: bool) -> (Result<Option<Audio>, Error>, Result<Option<Video>, Error>) {
+///
+/// The reader seeks to the given time in seconds before reading any packet,
+/// note it lands on the closest keyframe before it.
+pub fn spawn(path: &str, no_video: bool, seek: f64) -> (Result<Option<Audio>, Error>, Result<Option<Video>, Error>) {
 	let path = path.to_owned();
 
 	let (video_sender, video_receiver) = sync_channel(FRAMES);
@@ -110,6 +113,16 @@ pub fn spawn(path: &str, no_video: bool) -> (Result<Option<Audio>, Error>, Resul
 			}
 		};
 
+		// Seek to the requested time, the decoders haven't been sent any packet
+		// yet so they have nothing buffered to drop.
+		if seek > 0.0 {
+			let timestamp = (seek * 1_000_000.0) as i64;
+
+			if let Err(error) = context.seek(timestamp, .. timestamp) {
+				warn!("source: could not seek to {}s: {}", seek, error);
+			}
+		}
+
 		// Iterate over the packets.
 		for (stream, packet) in context.packets() {
 			if let Some((ref channel, index)) = video {
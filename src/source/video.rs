@@ -1,11 +1,14 @@
+use std::sync::Arc;
 use std::sync::mpsc::{SyncSender, Receiver, sync_channel};
 use std::thread;
 use std::mem;
 
-use ffmpeg::{Error, Stream, format, frame, decoder, time};
+use ffmpeg::{Error, Stream, format, frame, decoder};
 
 use super::{Decoder, Reader};
 use super::decoder::{get, try};
+use util::Clock;
+use util::clock::Wall;
 
 pub type D = Decoder<Details, frame::Video>;
 
@@ -35,9 +38,11 @@ impl Details {
 pub struct Video {
 	channel: Receiver<D>,
 	details: Details,
+	clock:   Arc<Clock>,
 
 	done:    bool,
 	start:   f64,
+	paused:  Option<f64>,
 	current: frame::Video,
 	next:    frame::Video,
 }
@@ -115,14 +120,22 @@ impl Video {
 		Video {
 			done:    false,
 			start:   -1.0,
+			paused:  None,
 			current: get(&channel).unwrap().unwrap(),
 			next:    get(&channel).unwrap().unwrap(),
 
 			channel: channel,
 			details: details,
+			clock:   Arc::new(Wall),
 		}
 	}
 
+	/// Synchronizes on the given clock instead of the wall clock.
+	pub fn with_clock(mut self, clock: Arc<Clock>) -> Self {
+		self.clock = clock;
+		self
+	}
+
 	/// Gets the format of the source.
 	pub fn format(&self) -> format::Pixel {
 		self.details.format
@@ -149,19 +162,42 @@ impl Video {
 	}
 
 	/// Sets the synchronized start time.
+	///
+	/// Frames are synchronized on their timestamp, so when starting from an
+	/// offset the start time has to be moved back by it.
 	pub fn start(&mut self, time: f64) {
 		self.start = time;
 	}
 
+	/// Pauses the synchronization at the given time.
+	pub fn pause(&mut self, time: f64) {
+		if self.paused.is_none() {
+			self.paused = Some(time);
+		}
+	}
+
+	/// Resumes the synchronization at the given time, the start time is moved
+	/// forward by the time spent paused.
+	pub fn resume(&mut self, time: f64) {
+		if let Some(paused) = self.paused.take() {
+			self.start += time - paused;
+		}
+	}
+
 	/// Synchronizes the source to get the current frame.
 	pub fn sync(&mut self) {
+		// Keep the current frame while paused.
+		if self.paused.is_some() {
+			return;
+		}
+
 		loop {
 			if self.done {
 				break;
 			}
 
 			// Get how much time has passed since the start in seconds.
-			let time = time::relative() as f64 / 1_000_000.0 - self.start;
+			let time = self.clock.now() - self.start;
 
 			// Normalize the timestamp with the time base.
 			let pts = self.next.timestamp().unwrap_or(0) as f64 * self.details.time_base;
@@ -1,5 +1,11 @@
 use ffmpeg::Rational;
 
+/// The length of the long side of the playfield in logical units.
+///
+/// The playfield is always laid out vertically, horizontal aspects are rotated
+/// when rendering, so the long side is always the height.
+pub const LONG: u32 = 640;
+
 pub trait Aspect {
 	fn is_vertical(&self) -> bool;
 	fn is_horizontal(&self) -> bool;
@@ -9,8 +15,9 @@ pub trait Aspect {
 }
 
 impl Aspect for Rational {
+	// Squares count as vertical since they need no rotation.
 	fn is_vertical(&self) -> bool {
-		self.0 < self.1
+		self.0 <= self.1
 	}
 
 	fn is_horizontal(&self) -> bool {
@@ -18,20 +25,20 @@ impl Aspect for Rational {
 	}
 
 	fn height(&self) -> u32 {
-		match self {
-			&Rational(3, 4)  => 640,
-			&Rational(16, 9) => 640,
-
-			_ => unreachable!()
-		}
+		LONG
 	}
 
 	fn width(&self) -> u32 {
-		match self {
-			&Rational(3, 4)  => 480,
-			&Rational(16, 9) => 360,
+		let a = self.0.abs() as u64;
+		let b = self.1.abs() as u64;
 
-			_ => unreachable!()
+		if a == 0 || b == 0 {
+			return LONG;
 		}
+
+		let (short, long) = if a < b { (a, b) } else { (b, a) };
+
+		// Round to the nearest unit.
+		((LONG as u64 * short * 2 + long) / (long * 2)) as u32
 	}
 }
@@ -0,0 +1,226 @@
+use std::f64;
+use std::sync::{Mutex, Condvar};
+
+use ffmpeg::time;
+
+/// A source of time in seconds, so the analyzer, video and music don't have
+/// to follow the wall clock.
+pub trait Clock: Send + Sync {
+	/// The current time.
+	fn now(&self) -> f64;
+
+	/// Waits for the given seconds to pass on the clock.
+	fn sleep(&self, seconds: f64);
+}
+
+/// The wall clock.
+pub struct Wall;
+
+impl Clock for Wall {
+	fn now(&self) -> f64 {
+		time::relative() as f64 / 1_000_000.0
+	}
+
+	fn sleep(&self, seconds: f64) {
+		if seconds > 0.0 {
+			time::sleep((seconds * 1_000_000.0) as u32).unwrap();
+		}
+	}
+}
+
+/// A clock moved by hand, sleeping on it waits for someone else to move it
+/// far enough, so a simulation can step through a song as fast as it wants.
+pub struct Manual {
+	time:    Mutex<f64>,
+	changed: Condvar,
+}
+
+impl Manual {
+	pub fn new(time: f64) -> Self {
+		Manual {
+			time:    Mutex::new(time),
+			changed: Condvar::new(),
+		}
+	}
+
+	/// Moves the clock to the given time.
+	pub fn set(&self, time: f64) {
+		*self.time.lock().unwrap() = time;
+		self.changed.notify_all();
+	}
+
+	/// Moves the clock forward by the given seconds.
+	pub fn advance(&self, seconds: f64) {
+		*self.time.lock().unwrap() += seconds;
+		self.changed.notify_all();
+	}
+}
+
+impl Clock for Manual {
+	fn now(&self) -> f64 {
+		*self.time.lock().unwrap()
+	}
+
+	fn sleep(&self, seconds: f64) {
+		let mut time = self.time.lock().unwrap();
+		let until    = *time + seconds;
+
+		while *time < until {
+			time = self.changed.wait(time).unwrap();
+		}
+	}
+}
+
+/// How far in seconds the audio can be from the clock before it's followed
+/// right away instead of little by little.
+const SNAP: f64 = 0.1;
+
+/// How much of the drift is corrected on every update.
+const CORRECTION: f64 = 0.1;
+
+/// A clock following the playback position of the audio.
+///
+/// The position is only known every so often and not very precisely, in
+/// between it moves along with the inner clock, and small drifts are
+/// corrected a little at every update so the time doesn't jitter, nor does it
+/// ever go backwards.
+pub struct Audio<C: Clock> {
+	inner:    C,
+	position: Mutex<Option<(f64, f64)>>,
+	last:     Mutex<f64>,
+}
+
+impl<C: Clock> Audio<C> {
+	pub fn new(inner: C) -> Self {
+		Audio {
+			inner:    inner,
+			position: Mutex::new(None),
+			last:     Mutex::new(f64::NEG_INFINITY),
+		}
+	}
+
+	/// Tells the clock the audio is at the given time right now.
+	pub fn update(&self, time: f64) {
+		let     now      = self.inner.now();
+		let mut position = self.position.lock().unwrap();
+
+		let current = match *position {
+			Some((last, at)) =>
+				last + (now - at),
+
+			None =>
+				now,
+		};
+
+		let drift = time - current;
+
+		*position = if drift.abs() > SNAP {
+			Some((time, now))
+		}
+		else {
+			Some((current + drift * CORRECTION, now))
+		};
+	}
+}
+
+impl<C: Clock> Clock for Audio<C> {
+	fn now(&self) -> f64 {
+		let time = match *self.position.lock().unwrap() {
+			Some((time, at)) =>
+				time + (self.inner.now() - at),
+
+			None =>
+				self.inner.now(),
+		};
+
+		let mut last = self.last.lock().unwrap();
+
+		if time > *last {
+			*last = time;
+		}
+
+		*last
+	}
+
+	fn sleep(&self, seconds: f64) {
+		self.inner.sleep(seconds)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::sync::mpsc::channel;
+	use std::thread;
+	use std::time::Duration;
+
+	use super::{Clock, Manual, Audio};
+
+	fn close(a: f64, b: f64) -> bool {
+		(a - b).abs() < 1e-9
+	}
+
+	#[test]
+	fn manual_sleep() {
+		let clock              = Arc::new(Manual::new(0.0));
+		let (sender, receiver) = channel();
+
+		{
+			let clock = clock.clone();
+
+			thread::spawn(move || {
+				clock.sleep(1.0);
+				sender.send(clock.now()).unwrap();
+			});
+		}
+
+		// The sleeper may start at any time, so keep moving the clock until it
+		// wakes up.
+		loop {
+			clock.advance(0.5);
+
+			if let Ok(time) = receiver.try_recv() {
+				assert!(time >= 1.0);
+				break;
+			}
+
+			thread::sleep(Duration::from_millis(1));
+		}
+	}
+
+	#[test]
+	fn audio_snap() {
+		let clock = Audio::new(Manual::new(0.0));
+
+		clock.update(5.0);
+		assert!(close(clock.now(), 5.0));
+
+		clock.inner.advance(1.0);
+		assert!(close(clock.now(), 6.0));
+	}
+
+	#[test]
+	fn audio_drift() {
+		let clock = Audio::new(Manual::new(0.0));
+
+		clock.update(0.0);
+		clock.inner.advance(1.0);
+		clock.update(1.05);
+
+		assert!(close(clock.now(), 1.005));
+	}
+
+	#[test]
+	fn audio_monotonic() {
+		let clock = Audio::new(Manual::new(0.0));
+
+		clock.update(10.0);
+		assert!(close(clock.now(), 10.0));
+
+		clock.update(9.95);
+		assert!(close(clock.now(), 10.0));
+
+		clock.inner.advance(1.0);
+		assert!(close(clock.now(), 10.995));
+	}
+}
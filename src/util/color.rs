@@ -14,6 +14,28 @@ impl Color {
 	pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
 		Color(Rgba { data: [r, g, b, a] })
 	}
+
+	/// Creates a color from hue in degrees, saturation and value between 0.0
+	/// and 1.0.
+	pub fn hsv(h: f32, s: f32, v: f32) -> Color {
+		let h = ((h % 360.0) + 360.0) % 360.0 / 60.0;
+		let c = v * s;
+		let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+		let m = v - c;
+
+		let (r, g, b) = match h as u32 {
+			0 => (c, x, 0.0),
+			1 => (x, c, 0.0),
+			2 => (0.0, c, x),
+			3 => (0.0, x, c),
+			4 => (x, 0.0, c),
+			_ => (c, 0.0, x),
+		};
+
+		Color::rgb(((r + m) * 255.0).round() as u8,
+		           ((g + m) * 255.0).round() as u8,
+		           ((b + m) * 255.0).round() as u8)
+	}
 }
 
 impl Default for Color {
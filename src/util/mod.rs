@@ -12,6 +12,9 @@ pub use self::aspect::Aspect;
 mod ring;
 pub use self::ring::Ring;
 
+pub mod clock;
+pub use self::clock::Clock;
+
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum Fill {
 	Color(Color),